@@ -0,0 +1,216 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use fformat_utils::{cp1251, glob::Pattern, native::conventional_from_native_lossy};
+use nom_prelude::prepare::{self, PrepareError};
+
+const GLOB_CHARS: &[char] = &['*', '?', '['];
+
+/// What directories expand to, the rest of a data folder is binary assets.
+pub const TEXT_EXTENSIONS: &[&str] = &["fopro", "fomap", "ini", "cfg", "txt", "lst"];
+
+fn is_text(file: &Path) -> bool {
+    file.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            TEXT_EXTENSIONS
+                .iter()
+                .any(|text| ext.eq_ignore_ascii_case(text))
+        })
+}
+
+/// Files named by `args`: plain files, text files in directories (recursively) and
+/// globs like `data/**/*.fopro`, matched case-insensitively against the part after the
+/// literal directory prefix. Sorted within each argument, duplicates dropped.
+pub fn expand(args: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for arg in args {
+        let found = if arg.contains(GLOB_CHARS) {
+            let (base, pattern) = split_glob(arg);
+            let pattern = Pattern::new(&pattern).map_err(|err| format!("{}: {}", arg, err))?;
+            let found: Vec<PathBuf> = walk(&base)
+                .map_err(|err| format!("{}: {}", base.display(), err))?
+                .into_iter()
+                .filter(|file| match conventional_from_native_lossy(&base, file) {
+                    Ok(path) => pattern.matches(path.as_str()),
+                    Err(_) => false,
+                })
+                .collect();
+            if found.is_empty() {
+                return Err(format!("{}: no files matched", arg));
+            }
+            found
+        } else {
+            let path = Path::new(arg);
+            if path.is_dir() {
+                let mut found = walk(path).map_err(|err| format!("{}: {}", arg, err))?;
+                found.retain(|file| is_text(file));
+                found
+            } else if path.is_file() {
+                vec![path.to_owned()]
+            } else {
+                return Err(format!("{}: no such file or directory", arg));
+            }
+        };
+        for file in found {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// `data/*/x.txt` -> (`data`, `*/x.txt`)
+fn split_glob(arg: &str) -> (PathBuf, String) {
+    let components: Vec<&str> = arg.split(['/', '\\']).collect();
+    let literal = components
+        .iter()
+        .position(|component| component.contains(GLOB_CHARS))
+        .unwrap_or(components.len());
+    let base = components[..literal].join("/");
+    let base = if base.is_empty() && arg.starts_with('/') {
+        PathBuf::from("/")
+    } else if base.is_empty() {
+        PathBuf::from(".")
+    } else {
+        PathBuf::from(base)
+    };
+    (base, components[literal..].join("/"))
+}
+
+pub fn walk(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    walk_into(dir, &mut files)?;
+    Ok(files)
+}
+
+fn walk_into(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if entry.file_type()?.is_dir() {
+            walk_into(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8 or UTF-16 as [`prepare::decode`] found it, BOM included.
+    Unicode(prepare::Encoding),
+    Cp1251,
+}
+
+/// UTF-8 or UTF-16 without the BOM, or cp1251 for the older files that are neither.
+/// Line endings are kept.
+pub fn read_text(path: &Path) -> io::Result<(String, Encoding)> {
+    let bytes = fs::read(path)?;
+    match prepare::decode(&bytes) {
+        Ok((text, encoding)) => Ok((text, Encoding::Unicode(encoding))),
+        Err(PrepareError::InvalidUtf8(_)) => Ok((cp1251::decode(&bytes), Encoding::Cp1251)),
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err.to_string())),
+    }
+}
+
+/// A cp1251 file is only written when every char maps back to a byte, so a byte
+/// like 0x98 that decoded to U+FFFD fails instead of turning into `?`.
+pub fn write_text(path: &Path, text: &str, encoding: Encoding) -> io::Result<()> {
+    match encoding {
+        Encoding::Unicode(encoding) => fs::write(path, prepare::encode(text, encoding)),
+        Encoding::Cp1251 => {
+            let bytes = text
+                .chars()
+                .map(|ch| cp1251::encode_char(ch).ok_or(ch))
+                .collect::<Result<Vec<u8>, char>>()
+                .map_err(|ch| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{:?} has no cp1251 byte, not written", ch),
+                    )
+                })?;
+            fs::write(path, bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_glob() {
+        assert_eq!(
+            split_glob("data/proto/**/*.fopro"),
+            (PathBuf::from("data/proto"), "**/*.fopro".to_owned())
+        );
+        assert_eq!(
+            split_glob("data\\*\\x.txt"),
+            (PathBuf::from("data"), "*/x.txt".to_owned())
+        );
+        assert_eq!(
+            split_glob("*.ini"),
+            (PathBuf::from("."), "*.ini".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let root = std::env::temp_dir().join(format!("fformat_inputs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Proto/Items")).unwrap();
+        fs::write(root.join("Proto/Items/Gun.FOPRO"), "").unwrap();
+        fs::write(root.join("Proto/Items/readme.txt"), "").unwrap();
+        fs::write(root.join("Proto/Base.fopro"), "").unwrap();
+
+        let glob = format!("{}/proto/**/*.fopro", root.display());
+        let files = expand(&[glob.replace("/proto/", "/Proto/")]).unwrap();
+        assert_eq!(
+            files,
+            [
+                root.join("Proto/Base.fopro"),
+                root.join("Proto/Items/Gun.FOPRO")
+            ]
+        );
+        fs::write(root.join("Proto/Items/Gun.frm"), [0u8, 1, 2]).unwrap();
+        let dir = root.join("Proto").display().to_string();
+        assert_eq!(expand(&[dir.clone(), dir]).unwrap().len(), 3);
+        assert!(expand(&[format!("{}/*.ini", root.display())]).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_read_text() {
+        let root = std::env::temp_dir().join(format!("fformat_read_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("bom.fopro");
+        fs::write(&file, b"\xEF\xBB\xBF[Header]\r\nName = x\r\n").unwrap();
+        let (text, encoding) = read_text(&file).unwrap();
+        assert_eq!(text, "[Header]\r\nName = x\r\n");
+        assert_eq!(encoding, Encoding::Unicode(prepare::Encoding::Utf8Bom));
+        write_text(&file, "[Header]\r\n", encoding).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"\xEF\xBB\xBF[Header]\r\n");
+
+        fs::write(&file, b"Name = \xCF\xF0\xE8\n").unwrap();
+        assert_eq!(
+            read_text(&file).unwrap(),
+            ("Name = При\n".to_owned(), Encoding::Cp1251)
+        );
+        fs::write(&file, b"Name = \x98\xCF\n").unwrap();
+        let (text, encoding) = read_text(&file).unwrap();
+        assert_eq!(text, "Name = \u{FFFD}П\n");
+        assert!(write_text(&file, &text, encoding).is_err());
+        assert_eq!(fs::read(&file).unwrap(), b"Name = \x98\xCF\n");
+        write_text(&file, "Name = П\n", encoding).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"Name = \xCF\n");
+        fs::write(&file, b"\xFF\xFE[").unwrap();
+        assert!(read_text(&file).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
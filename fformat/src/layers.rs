@@ -0,0 +1,123 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use fformat_utils::{
+    archive::ZipPack, dir::DirIndex, make_path_conventional,
+    native::conventional_from_native_lossy, PathIndex,
+};
+
+use crate::inputs::walk;
+
+pub enum Layer {
+    Dir {
+        root: PathBuf,
+        index: DirIndex,
+    },
+    Zip {
+        path: PathBuf,
+        pack: ZipPack<BufReader<File>>,
+    },
+}
+
+impl Layer {
+    /// A data folder or a zip archive.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let err = |err: &dyn std::fmt::Display| format!("{}: {}", path.display(), err);
+        if path.is_dir() {
+            let files = walk(path).map_err(|e| err(&e))?;
+            let index = DirIndex::new(
+                files
+                    .iter()
+                    .filter_map(|file| conventional_from_native_lossy(path, file).ok())
+                    .map(|path| path.into_string()),
+            );
+            Ok(Layer::Dir {
+                root: path.to_owned(),
+                index,
+            })
+        } else {
+            let pack = ZipPack::open(path).map_err(|e| err(&e))?;
+            Ok(Layer::Zip {
+                path: path.to_owned(),
+                pack,
+            })
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Layer::Dir { root, .. } => root,
+            Layer::Zip { path, .. } => path,
+        }
+    }
+
+    pub fn index(&self) -> &dyn PathIndex {
+        match self {
+            Layer::Dir { index, .. } => index,
+            Layer::Zip { pack, .. } => pack,
+        }
+    }
+}
+
+/// Layers in priority order, the first one containing a path serves it.
+pub struct Layers(pub Vec<Layer>);
+
+impl Layers {
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<Self, String> {
+        paths
+            .iter()
+            .map(|path| Layer::open(path.as_ref()))
+            .collect::<Result<_, _>>()
+            .map(Layers)
+    }
+
+    /// Every layer containing `path`, the serving one first.
+    pub fn lookup(&self, path: &str) -> Vec<&Layer> {
+        let path = make_path_conventional(path);
+        self.0
+            .iter()
+            .filter(|layer| layer.index().contains_path(&path))
+            .collect()
+    }
+
+    /// Union of all layers, for suggestions.
+    pub fn merged(&self) -> DirIndex {
+        let mut merged = DirIndex::default();
+        for layer in &self.0 {
+            for path in layer.index().paths() {
+                merged.insert(path);
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let root = std::env::temp_dir().join(format!("fformat_layers_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("mod/Art")).unwrap();
+        fs::create_dir_all(root.join("base/art")).unwrap();
+        fs::write(root.join("mod/Art/Gun.frm"), "").unwrap();
+        fs::write(root.join("base/art/gun.frm"), "").unwrap();
+        fs::write(root.join("base/art/knife.frm"), "").unwrap();
+
+        let layers = Layers::open(&[root.join("mod"), root.join("base")]).unwrap();
+        let found = layers.lookup("ART\\GUN.FRM");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].path(), root.join("mod"));
+        assert_eq!(layers.lookup("art/knife.frm")[0].path(), root.join("base"));
+        assert!(layers.lookup("art/axe.frm").is_empty());
+        assert_eq!(layers.merged().len(), 2);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
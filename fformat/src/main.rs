@@ -0,0 +1,706 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+};
+
+use fformat_utils::{archive::ZipPack, glob::Pattern, suggest::suggest, ConventionalPath};
+use nom_prelude::{
+    diff::{diff_documents, diff_messages, Change, Target},
+    document::Document,
+    format::{format, FormatOptions, Separator},
+    merge::{merge, MergeOptions},
+    msg::MsgTable,
+    prepare::LineEnding,
+    schema::Schema,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    inputs::{expand, read_text, write_text},
+    layers::Layers,
+};
+
+mod inputs;
+mod layers;
+
+const USAGE: &str = "\
+usage: fformat <command> [options] <args>
+
+commands:
+    check [-s <schema>] <files>...    parse files and print diagnostics
+    dump <files>...                   print parsed files as JSON
+    fmt [--check | --write] [style] <files>...
+                                      re-emit files canonically
+    ls <archive> [pattern]            list archive entries
+    cat <archive> <path>...           write archive entries to stdout
+    extract <archive> <dir> [pattern] extract archive entries into dir
+    resolve -l <layer>... <path>...   show which layer serves a path
+    merge [-o <file>] [-L <size>] <base> <ours> <theirs>
+                                      merge by section and key into <ours>
+    diff [--json] <old> <new>         list changed sections, keys and messages
+
+fmt style: --align (line up `=` within sections), --sort (sort keys),
+--lf | --crlf (line endings, default: the most frequent one of each file),
+--spacing <n> (blank lines before sections, default 1).
+
+As a git merge driver:
+    [merge \"fformat\"]
+        driver = fformat merge -L %L %O %A %B
+
+check and diff read files ending in .msg as message tables, others as sections of
+keys. check validates the latter against <schema> when one is given.
+
+<files> are files, directories or globs like 'data/**/*.fopro'. Directories expand
+to their .fopro, .fomap, .ini, .cfg, .txt and .lst files.
+Layers are data folders or zip archives, highest priority first.
+
+exit codes: 0 ok, 1 problems found (or differences), 2 usage or I/O error
+";
+
+/// `Ok(false)` when the command ran but found problems.
+type Outcome = Result<bool, String>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = match run(&args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("fformat: {}", err);
+            2
+        }
+    };
+    process::exit(code);
+}
+
+fn run(args: &[String]) -> Outcome {
+    let (command, args) = match args.split_first() {
+        Some(split) => split,
+        None => return Err(format!("no command\n\n{}", USAGE)),
+    };
+    match command.as_str() {
+        "check" => check(&Args::parse(args, &[], &["-s", "--schema"])?),
+        "dump" => dump(&Args::parse(args, &[], &[])?),
+        "fmt" => fmt(&Args::parse(
+            args,
+            &["--check", "--write", "--align", "--sort", "--lf", "--crlf"],
+            &["--spacing"],
+        )?),
+        "ls" => ls(&Args::parse(args, &[], &[])?),
+        "cat" => cat(&Args::parse(args, &[], &[])?),
+        "extract" => extract(&Args::parse(args, &[], &[])?),
+        "resolve" => resolve(&Args::parse(args, &[], &["-l", "--layer"])?),
+        "merge" => merge_files(&Args::parse(
+            args,
+            &[],
+            &["-o", "--output", "-L", "--marker-size"],
+        )?),
+        "diff" => diff(&Args::parse(args, &["--json"], &[])?),
+        "help" | "-h" | "--help" => {
+            print!("{}", USAGE);
+            Ok(true)
+        }
+        _ => Err(format!("unknown command `{}`\n\n{}", command, USAGE)),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// `flags` take no value, `options` take the next argument; `--` ends both.
+    fn parse(args: &[String], flags: &[&str], options: &[&str]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positional.extend(args.cloned());
+                break;
+            } else if flags.contains(&arg.as_str()) {
+                parsed.flags.push(arg.clone());
+            } else if options.contains(&arg.as_str()) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("`{}` needs a value", arg))?;
+                parsed.options.push((arg.clone(), value.clone()));
+            } else if arg.starts_with('-') && arg.len() > 1 {
+                return Err(format!("unknown option `{}`\n\n{}", arg, USAGE));
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn values<'a>(&'a self, names: &'a [&str]) -> impl Iterator<Item = &'a str> + 'a {
+        self.options
+            .iter()
+            .filter(move |(name, _)| names.contains(&name.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn files(&self) -> Result<Vec<std::path::PathBuf>, String> {
+        if self.positional.is_empty() {
+            return Err(format!("no files given\n\n{}", USAGE));
+        }
+        expand(&self.positional)
+    }
+
+    /// Exactly `min..=max` positional arguments.
+    fn positional(&self, min: usize, max: usize) -> Result<&[String], String> {
+        if (min..=max).contains(&self.positional.len()) {
+            Ok(&self.positional)
+        } else {
+            Err(format!("wrong number of arguments\n\n{}", USAGE))
+        }
+    }
+}
+
+/// `.msg` files are message tables, the rest sections of keys.
+fn is_msg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("msg"))
+}
+
+fn check(args: &Args) -> Outcome {
+    let schema = match args.values(&["-s", "--schema"]).last() {
+        Some(path) => {
+            let (text, _) =
+                read_text(Path::new(path)).map_err(|err| format!("{}: {}", path, err))?;
+            Some(Schema::parse(&text).map_err(|err| format!("{}: {}", path, err))?)
+        }
+        None => None,
+    };
+    let files = args.files()?;
+    let (mut errors, mut warnings) = (0, 0);
+    for file in &files {
+        let (text, _) = read_text(file).map_err(|err| format!("{}: {}", file.display(), err))?;
+        let problems = if is_msg(file) {
+            check_messages(&text)
+        } else {
+            check_document(&text, schema.as_ref())
+        };
+        for problem in problems {
+            match problem {
+                Problem::Error(None, msg) => {
+                    errors += 1;
+                    println!("{}: error: {}", file.display(), msg);
+                }
+                Problem::Error(Some(line), msg) => {
+                    errors += 1;
+                    println!("{}:{}: error: {}", file.display(), line, msg);
+                }
+                Problem::Warning(line, msg) => {
+                    warnings += 1;
+                    println!("{}:{}: warning: {}", file.display(), line, msg);
+                }
+            }
+        }
+    }
+    eprintln!(
+        "checked {} files: {} errors, {} warnings",
+        files.len(),
+        errors,
+        warnings
+    );
+    Ok(errors == 0)
+}
+
+/// With the 1-based line, if there is one.
+#[derive(Debug, PartialEq)]
+enum Problem {
+    Error(Option<usize>, String),
+    Warning(usize, String),
+}
+
+fn check_document(text: &str, schema: Option<&Schema>) -> Vec<Problem> {
+    let doc = match Document::parse(text) {
+        Ok(doc) => doc,
+        Err(err) => return vec![Problem::Error(None, err.trim_end().to_owned())],
+    };
+    let mut problems = Vec::new();
+    for section in &doc.sections {
+        let mut seen: Vec<&str> = Vec::new();
+        for (key, _, line) in section.entries() {
+            if seen.contains(&key) {
+                problems.push(Problem::Warning(
+                    line.line,
+                    format!(
+                        "`{}` repeated in [{}], the last value wins",
+                        key,
+                        section.name()
+                    ),
+                ));
+            } else {
+                seen.push(key);
+            }
+        }
+    }
+    if let Some(Err(errs)) = schema.map(|schema| schema.validate(&doc)) {
+        problems.extend(
+            errs.into_iter()
+                .map(|err| Problem::Error(err.line, err.msg)),
+        );
+    }
+    problems
+}
+
+fn check_messages(text: &str) -> Vec<Problem> {
+    let table = match MsgTable::parse(text) {
+        Ok(table) => table,
+        Err(err) => return vec![Problem::Error(None, err.trim_end().to_owned())],
+    };
+    let mut problems = Vec::new();
+    let mut seen: Vec<u32> = Vec::new();
+    for message in &table.messages {
+        if seen.contains(&message.number) {
+            problems.push(Problem::Warning(
+                message.line,
+                format!("message {} repeated, the first one wins", message.number),
+            ));
+        } else {
+            seen.push(message.number);
+        }
+    }
+    problems
+}
+
+fn document_json(doc: &Document) -> Value {
+    let sections: Vec<Value> = doc
+        .sections
+        .iter()
+        .map(|section| {
+            let entries: Vec<Value> = section
+                .entries()
+                .map(|(key, value, line)| json!({ "key": key, "value": value, "line": line.line }))
+                .collect();
+            json!({
+                "name": section.name(),
+                "line": section.line(),
+                "entries": entries,
+            })
+        })
+        .collect();
+    json!({ "sections": sections })
+}
+
+fn dump(args: &Args) -> Outcome {
+    let mut ok = true;
+    let mut dumped = Vec::new();
+    for file in args.files()? {
+        let (text, _) = read_text(&file).map_err(|err| format!("{}: {}", file.display(), err))?;
+        match Document::parse(&text) {
+            Ok(doc) => {
+                let mut value = document_json(&doc);
+                value["path"] = json!(file.display().to_string());
+                dumped.push(value);
+            }
+            Err(err) => {
+                ok = false;
+                eprintln!("{}: error: {}", file.display(), err.trim_end());
+            }
+        }
+    }
+    let json = serde_json::to_string_pretty(&dumped).map_err(|err| err.to_string())?;
+    println!("{}", json);
+    Ok(ok)
+}
+
+fn format_options(args: &Args) -> Result<FormatOptions, String> {
+    let mut options = FormatOptions::default();
+    if args.flag("--align") {
+        options.separator = Separator::Aligned;
+    }
+    options.sort_keys = args.flag("--sort");
+    options.line_ending = match (args.flag("--lf"), args.flag("--crlf")) {
+        (true, true) => return Err("`--lf` and `--crlf` exclude each other".into()),
+        (true, false) => Some(LineEnding::Lf),
+        (false, true) => Some(LineEnding::CrLf),
+        (false, false) => None,
+    };
+    if let Some(spacing) = args.values(&["--spacing"]).last() {
+        options.section_spacing = spacing
+            .parse()
+            .map_err(|_| format!("`--spacing` needs a number, got `{}`", spacing))?;
+    }
+    Ok(options)
+}
+
+fn fmt(args: &Args) -> Outcome {
+    let (check, write) = (args.flag("--check"), args.flag("--write"));
+    if check && write {
+        return Err("`--check` and `--write` exclude each other".into());
+    }
+    let options = format_options(args)?;
+    let mut ok = true;
+    for file in args.files()? {
+        let display = file.display();
+        let (text, encoding) = read_text(&file).map_err(|err| format!("{}: {}", display, err))?;
+        let formatted = match format(&text, &options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                ok = false;
+                eprintln!("{}: error: {}", display, err.trim_end());
+                continue;
+            }
+        };
+        if check {
+            if formatted != text {
+                ok = false;
+                println!("{}", display);
+            }
+        } else if write {
+            if formatted != text {
+                write_text(&file, &formatted, encoding)
+                    .map_err(|err| format!("{}: {}", display, err))?;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    Ok(ok)
+}
+
+fn open_pack(path: &str) -> Result<ZipPack<io::BufReader<fs::File>>, String> {
+    ZipPack::open(path).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Sorted archive paths, all of them or those matching `pattern`.
+fn pack_paths<R: io::Read + io::Seek>(
+    pack: &ZipPack<R>,
+    pattern: Option<&String>,
+) -> Result<Vec<String>, String> {
+    let pattern = pattern
+        .map(|pattern| Pattern::new(pattern).map_err(|err| format!("{}: {}", pattern, err)))
+        .transpose()?;
+    let mut paths: Vec<String> = pack
+        .paths()
+        .filter(|path| pattern.as_ref().is_none_or(|pattern| pattern.matches(path)))
+        .map(str::to_owned)
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn ls(args: &Args) -> Outcome {
+    let positional = args.positional(1, 2)?;
+    let pack = open_pack(&positional[0])?;
+    let paths = pack_paths(&pack, positional.get(1))?;
+    for path in &paths {
+        println!("{}", path);
+    }
+    Ok(!paths.is_empty() || positional.len() == 1)
+}
+
+fn cat(args: &Args) -> Outcome {
+    let positional = args.positional(2, usize::MAX)?;
+    let mut pack = open_pack(&positional[0])?;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut ok = true;
+    for path in &positional[1..] {
+        let path = fformat_utils::make_path_conventional(path);
+        if !pack.contains(&path) {
+            ok = false;
+            eprintln!("{}: not found in {}", path, positional[0]);
+            for suggestion in suggest(&path, &pack, 3) {
+                eprintln!("  did you mean {}?", suggestion.path);
+            }
+            continue;
+        }
+        let bytes = pack
+            .read(&path)
+            .map_err(|err| format!("{}: {}", path, err))?;
+        stdout.write_all(&bytes).map_err(|err| err.to_string())?;
+    }
+    Ok(ok)
+}
+
+fn extract(args: &Args) -> Outcome {
+    let positional = args.positional(2, 3)?;
+    let mut pack = open_pack(&positional[0])?;
+    let root = Path::new(&positional[1]);
+    let mut ok = true;
+    for path in pack_paths(&pack, positional.get(2))? {
+        let target = match ConventionalPath::new(&path, pack.folding())
+            .ok_or_else(|| format!("{}: not a conventional path", path))
+            .and_then(|conventional| {
+                conventional
+                    .extraction_target(root)
+                    .map_err(|err| err.to_string())
+            }) {
+            Ok(target) => target,
+            Err(err) => {
+                ok = false;
+                eprintln!("skipped {}", err);
+                continue;
+            }
+        };
+        let bytes = pack
+            .read(&path)
+            .map_err(|err| format!("{}: {}", path, err))?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|err| format!("{}: {}", parent.display(), err))?;
+        }
+        fs::write(&target, bytes).map_err(|err| format!("{}: {}", target.display(), err))?;
+        println!("{}", target.display());
+    }
+    Ok(ok)
+}
+
+fn resolve(args: &Args) -> Outcome {
+    let layers: Vec<&str> = args.values(&["-l", "--layer"]).collect();
+    if layers.is_empty() {
+        return Err(format!("no layers given\n\n{}", USAGE));
+    }
+    let layers = Layers::open(&layers)?;
+    let mut merged = None;
+    let mut ok = true;
+    for path in args.positional(1, usize::MAX)? {
+        let found = layers.lookup(path);
+        match found.split_first() {
+            Some((serving, shadowed)) => {
+                println!("{}: {}", path, serving.path().display());
+                for layer in shadowed {
+                    println!("  shadows {}", layer.path().display());
+                }
+            }
+            None => {
+                ok = false;
+                println!("{}: not found", path);
+                let merged = merged.get_or_insert_with(|| layers.merged());
+                for suggestion in suggest(path, merged, 3) {
+                    println!("  did you mean {}?", suggestion.path);
+                }
+            }
+        }
+    }
+    Ok(ok)
+}
+
+/// Writes the result into `<ours>` unless `-o` is given, conflicts leave markers and
+/// fail like git expects from a merge driver.
+fn merge_files(args: &Args) -> Outcome {
+    let positional = args.positional(3, 3)?;
+    let read =
+        |path: &String| read_text(Path::new(path)).map_err(|err| format!("{}: {}", path, err));
+    let (base, _) = read(&positional[0])?;
+    let (ours, encoding) = read(&positional[1])?;
+    let (theirs, _) = read(&positional[2])?;
+    let mut options = MergeOptions::default();
+    if let Some(size) = args.values(&["-L", "--marker-size"]).last() {
+        options.marker_size = size
+            .parse()
+            .map_err(|_| format!("`-L` needs a number, got `{}`", size))?;
+    }
+    let merged = merge(&base, &ours, &theirs, &options)?;
+    let output = args
+        .values(&["-o", "--output"])
+        .last()
+        .unwrap_or(&positional[1]);
+    write_text(Path::new(output), &merged.text, encoding)
+        .map_err(|err| format!("{}: {}", output, err))?;
+    for conflict in &merged.conflicts {
+        eprintln!("{}: conflict at {}", output, conflict);
+    }
+    Ok(merged.is_clean())
+}
+
+fn change_json(change: &Change) -> Value {
+    let (section, index, key, number) = match change.target {
+        Target::Section { name, index } => (Some(name), index, None, None),
+        Target::Key {
+            section,
+            index,
+            key,
+        } => (Some(section), index, Some(key), None),
+        Target::Message { number, index } => (None, index, None, Some(number)),
+    };
+    json!({
+        "kind": change.kind.as_str(),
+        "section": section,
+        "index": index,
+        "key": key,
+        "number": number,
+        "old": change.old,
+        "new": change.new,
+        "old_line": change.old_line,
+        "new_line": change.new_line,
+    })
+}
+
+/// Differences fail like `diff(1)`.
+fn diff(args: &Args) -> Outcome {
+    let positional = args.positional(2, 2)?;
+    let read = |path: &String| {
+        read_text(Path::new(path))
+            .map(|(text, _)| text)
+            .map_err(|err| format!("{}: {}", path, err))
+    };
+    let (old, new) = (read(&positional[0])?, read(&positional[1])?);
+    let is_msg = positional.iter().all(|path| is_msg(Path::new(path)));
+    let (old_msg, new_msg, old_doc, new_doc);
+    let changes = if is_msg {
+        old_msg = MsgTable::parse(&old)
+            .map_err(|err| format!("{}: {}", positional[0], err.trim_end()))?;
+        new_msg = MsgTable::parse(&new)
+            .map_err(|err| format!("{}: {}", positional[1], err.trim_end()))?;
+        diff_messages(&old_msg, &new_msg)
+    } else {
+        old_doc = Document::parse(&old)
+            .map_err(|err| format!("{}: {}", positional[0], err.trim_end()))?;
+        new_doc = Document::parse(&new)
+            .map_err(|err| format!("{}: {}", positional[1], err.trim_end()))?;
+        diff_documents(&old_doc, &new_doc)
+    };
+    if args.flag("--json") {
+        let changes: Vec<Value> = changes.iter().map(change_json).collect();
+        let json = serde_json::to_string_pretty(&changes).map_err(|err| err.to_string())?;
+        println!("{}", json);
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+    }
+    Ok(changes.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|&arg| arg.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_args() {
+        let args = Args::parse(
+            &strings(&["-l", "data", "--check", "a.txt", "--", "-b.txt"]),
+            &["--check"],
+            &["-l"],
+        )
+        .unwrap();
+        assert!(args.flag("--check"));
+        assert_eq!(args.values(&["-l"]).collect::<Vec<_>>(), ["data"]);
+        assert_eq!(args.positional, ["a.txt", "-b.txt"]);
+        assert!(Args::parse(&strings(&["--nope"]), &[], &[]).is_err());
+        assert!(Args::parse(&strings(&["-l"]), &[], &["-l"]).is_err());
+        assert!(run(&strings(&["frobnicate"])).is_err());
+
+        let args = Args::parse(
+            &strings(&["--align", "--crlf", "--spacing", "2"]),
+            &["--align", "--crlf"],
+            &["--spacing"],
+        )
+        .unwrap();
+        let options = format_options(&args).unwrap();
+        assert_eq!(options.separator, Separator::Aligned);
+        assert_eq!(options.line_ending, Some(LineEnding::CrLf));
+        assert_eq!(options.section_spacing, 2);
+    }
+
+    #[test]
+    fn test_check() {
+        let schema = Schema::parse("[Header]\nMaxX = int\n").unwrap();
+        assert_eq!(
+            check_document("[Header]\nMaxX = 1\nMaxX = 2\n", Some(&schema)),
+            [
+                Problem::Warning(3, "`MaxX` repeated in [Header], the last value wins".into()),
+                Problem::Error(Some(3), "`MaxX` in [Header]: repeated key".into()),
+            ]
+        );
+        assert!(matches!(
+            check_document("[Header]\nMaxX = x\n", Some(&schema)).as_slice(),
+            [Problem::Error(Some(2), _)]
+        ));
+        assert!(check_document("[Header]\nMaxX = x\n", None).is_empty());
+        assert_eq!(
+            check_messages("{100}{}{One}\n{101}{}{Two}\n{100}{}{Again}\n"),
+            [Problem::Warning(
+                3,
+                "message 100 repeated, the first one wins".into()
+            )]
+        );
+        assert!(matches!(
+            check_messages("{100}{}{Open\n").as_slice(),
+            [Problem::Error(None, _)]
+        ));
+    }
+
+    #[test]
+    fn test_merge_files() {
+        let root = std::env::temp_dir().join(format!("fformat_merge_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let files = ["base", "ours", "theirs"].map(|name| root.join(name));
+        fs::write(&files[0], "[A]\nX = 1\nY = 1\n").unwrap();
+        fs::write(&files[1], "[A]\nX = 2\nY = 1\n").unwrap();
+        fs::write(&files[2], "[A]\nX = 1\nY = 2\n").unwrap();
+        let paths: Vec<String> = files
+            .iter()
+            .map(|file| file.display().to_string())
+            .collect();
+        let mut args = strings(&["merge"]);
+        args.extend(paths.iter().cloned());
+        assert_eq!(run(&args), Ok(true));
+        assert_eq!(
+            fs::read_to_string(&files[1]).unwrap(),
+            "[A]\nX = 2\nY = 2\n"
+        );
+
+        fs::write(&files[2], "[A]\nX = 3\nY = 1\n").unwrap();
+        assert_eq!(run(&args), Ok(false));
+        assert!(fs::read_to_string(&files[1])
+            .unwrap()
+            .contains("<<<<<<< ours"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_diff() {
+        let root = std::env::temp_dir().join(format!("fformat_diff_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let files = ["old.MSG", "new.MSG"].map(|name| root.join(name));
+        fs::write(&files[0], "{100}{}{Hello}\n").unwrap();
+        fs::write(&files[1], "{100}{}{Hello}\n").unwrap();
+        let mut args = strings(&["diff"]);
+        args.extend(files.iter().map(|file| file.display().to_string()));
+        assert_eq!(run(&args), Ok(true));
+        fs::write(&files[1], "{100}{}{Hi}\n").unwrap();
+        assert_eq!(run(&args), Ok(false));
+        fs::write(&files[1], "{1x}\n").unwrap();
+        assert!(run(&args).is_err());
+
+        let changes = diff_documents(
+            &Document::parse("[Objects]\nMapX = 10\n").unwrap(),
+            &Document::parse("[Objects]\nMapX = 12\n").unwrap(),
+        );
+        assert_eq!(
+            change_json(&changes[0]),
+            json!({
+                "kind": "changed",
+                "section": "Objects",
+                "index": null,
+                "key": "MapX",
+                "number": null,
+                "old": "10",
+                "new": "12",
+                "old_line": 2,
+                "new_line": 2,
+            })
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,22 @@
+use std::{io, process};
+
+use crate::server::Server;
+
+mod rpc;
+mod server;
+mod text;
+mod workspace;
+
+/// Speaks LSP over stdin and stdout, editors usually pass `--stdio` which is ignored.
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let code = match Server::new().run(stdin.lock(), stdout.lock()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("fformat_lsp: {}", err);
+            2
+        }
+    };
+    process::exit(code);
+}
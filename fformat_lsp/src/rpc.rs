@@ -0,0 +1,106 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// Larger bodies are rejected before allocating them.
+pub const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+/// Body of the next `Content-Length` framed message, `None` at the end of input.
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if input.read_line(&mut header)? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+            };
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            match length {
+                Some(_) => break,
+                None => return Err(invalid_data("missing Content-Length header")),
+            }
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let parsed = value.trim().parse::<usize>().map_err(invalid_data)?;
+                if parsed > MAX_MESSAGE_LEN {
+                    return Err(invalid_data(format!(
+                        "Content-Length {} exceeds {}",
+                        parsed, MAX_MESSAGE_LEN
+                    )));
+                }
+                length = Some(parsed);
+            }
+        }
+    }
+    let mut body = vec![0; length.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+pub fn response(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_framing() {
+        let mut framed = Vec::new();
+        write_message(&mut framed, &notification("exit", Value::Null)).unwrap();
+        write_message(&mut framed, &json!({ "text": "Имя" })).unwrap();
+        let mut input = Cursor::new(framed);
+        let first = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&first).unwrap()["method"],
+            "exit"
+        );
+        let second = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&second).unwrap()["text"],
+            "Имя"
+        );
+        assert!(read_message(&mut input).unwrap().is_none());
+
+        let mut truncated = Cursor::new(b"Content-Length: 10\r\n\r\n{}".to_vec());
+        assert!(read_message(&mut truncated).is_err());
+        let mut headless = Cursor::new(b"\r\n{}".to_vec());
+        assert!(read_message(&mut headless).is_err());
+        let mut huge = Cursor::new(b"Content-Length: 99999999999\r\n\r\n{}".to_vec());
+        assert_eq!(
+            read_message(&mut huge).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,803 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use fformat_utils::{glob::Pattern, make_path_conventional};
+use nom_prelude::{
+    document::{Document, Line, Section},
+    error_line,
+    schema::{KeySchema, Schema, Type},
+};
+use serde_json::{json, Value};
+
+use crate::{
+    rpc::{
+        error_response, notification, read_message, response, write_message, INVALID_PARAMS,
+        INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR,
+    },
+    text::{span, LineIndex},
+    workspace::{id_entries, path_from_uri, uri_from_path, IdEntry, Workspace},
+};
+
+const ERROR: u32 = 1;
+const WARNING: u32 = 2;
+
+// CompletionItemKind and SymbolKind
+const COMPLETION_PROPERTY: u32 = 10;
+const COMPLETION_MODULE: u32 = 9;
+const COMPLETION_ENUM_MEMBER: u32 = 20;
+const SYMBOL_NAMESPACE: u32 = 3;
+const SYMBOL_PROPERTY: u32 = 7;
+
+type Failure = (i64, String);
+
+/// Keys whose values other entries refer to when no `idKeys` option is given.
+const DEFAULT_ID_KEYS: &[&str] = &["Pid", "Id"];
+
+pub struct Server {
+    workspace: Option<Workspace>,
+    /// Open documents by URI.
+    documents: BTreeMap<String, String>,
+    schemas: Vec<(Pattern, Schema)>,
+    id_keys: Vec<String>,
+    /// Notifications to send after the current reply.
+    outbox: Vec<Value>,
+    shutdown: bool,
+    exit: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            workspace: None,
+            documents: BTreeMap::new(),
+            schemas: Vec::new(),
+            id_keys: DEFAULT_ID_KEYS.iter().map(|&key| key.to_owned()).collect(),
+            outbox: Vec::new(),
+            shutdown: false,
+            exit: false,
+        }
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves until `exit` or the end of input, returns the process exit code.
+    pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> io::Result<i32> {
+        while let Some(body) = read_message(&mut input)? {
+            let replies = match serde_json::from_slice(&body) {
+                Ok(message) => self.handle(&message),
+                Err(err) => vec![error_response(&Value::Null, PARSE_ERROR, &err.to_string())],
+            };
+            for reply in &replies {
+                write_message(&mut output, reply)?;
+            }
+            if self.exit {
+                break;
+            }
+        }
+        Ok(if self.shutdown { 0 } else { 1 })
+    }
+
+    /// Replies to one message, the response first.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let params = &message["params"];
+        let mut replies = match (message["method"].as_str(), message.get("id")) {
+            (Some(method), Some(id)) => vec![match self.request(method, params) {
+                Ok(result) => response(id, result),
+                Err((code, msg)) => error_response(id, code, &msg),
+            }],
+            (Some(method), None) => {
+                self.notification(method, params);
+                Vec::new()
+            }
+            // responses, we send no requests
+            (None, _) => Vec::new(),
+        };
+        replies.append(&mut self.outbox);
+        replies
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, Failure> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, "the server is shut down".into()));
+        }
+        match method {
+            "initialize" => Ok(self.initialize(params)),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.at_position(params, Self::hover),
+            "textDocument/definition" => self.at_position(params, Self::definition),
+            "textDocument/completion" => self.at_position(params, Self::completion),
+            "textDocument/documentSymbol" => {
+                let uri = text_document_uri(params)?;
+                Ok(match self.documents.get(uri) {
+                    Some(text) => document_symbols(text),
+                    None => Value::Null,
+                })
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        match method {
+            "exit" => self.exit = true,
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                if let (Some(uri), Some(text)) =
+                    (document["uri"].as_str(), document["text"].as_str())
+                {
+                    self.documents.insert(uri.to_owned(), text.to_owned());
+                    self.publish_diagnostics(uri);
+                }
+            }
+            "textDocument/didChange" => {
+                // full sync, the last change holds the whole text
+                let uri = params["textDocument"]["uri"].as_str();
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.documents.insert(uri.to_owned(), text.to_owned());
+                    self.publish_diagnostics(uri);
+                }
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.documents.remove(uri);
+                    self.outbox.push(notification(
+                        "textDocument/publishDiagnostics",
+                        json!({ "uri": uri, "diagnostics": [] }),
+                    ));
+                }
+            }
+            "textDocument/didSave" | "workspace/didChangeWatchedFiles" => {
+                if let Some(workspace) = &mut self.workspace {
+                    workspace.invalidate();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Options: `{"schemas": {"<glob>": "<schema file>"}, "idKeys": ["Pid"]}`, schema
+    /// files relative to the workspace root.
+    fn initialize(&mut self, params: &Value) -> Value {
+        let root = params["rootUri"]
+            .as_str()
+            .and_then(path_from_uri)
+            .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+        let options = &params["initializationOptions"];
+        if let Some(keys) = options["idKeys"].as_array() {
+            self.id_keys = keys
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect();
+        }
+        self.workspace = root.map(|root| Workspace::new(root, self.id_keys.clone()));
+        if let Some(schemas) = options["schemas"].as_object() {
+            for (glob, file) in schemas {
+                let file = Path::new(file.as_str().unwrap_or_default());
+                let file = match &self.workspace {
+                    Some(workspace) => workspace.root().join(file),
+                    None => file.to_owned(),
+                };
+                match self.load_schema(glob, &file) {
+                    Ok(schema) => self.schemas.push(schema),
+                    Err(err) => self.outbox.push(notification(
+                        "window/showMessage",
+                        json!({ "type": ERROR, "message": err }),
+                    )),
+                }
+            }
+        }
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                "hoverProvider": true,
+                "definitionProvider": true,
+                "completionProvider": { "triggerCharacters": ["["] },
+                "documentSymbolProvider": true,
+            },
+            "serverInfo": { "name": "fformat_lsp", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn load_schema(&self, glob: &str, file: &Path) -> Result<(Pattern, Schema), String> {
+        let pattern = Pattern::new(glob).map_err(|err| format!("{}: {}", glob, err))?;
+        let text =
+            std::fs::read_to_string(file).map_err(|err| format!("{}: {}", file.display(), err))?;
+        let schema = Schema::parse(&text).map_err(|err| format!("{}: {}", file.display(), err))?;
+        Ok((pattern, schema))
+    }
+
+    /// Conventional path of a document, its file name outside of the workspace.
+    fn conventional(&self, uri: &str) -> String {
+        let path = match path_from_uri(uri) {
+            Some(path) => path,
+            None => return make_path_conventional(uri),
+        };
+        self.workspace
+            .as_ref()
+            .and_then(|workspace| workspace.conventional(&path))
+            .or_else(|| {
+                path.file_name()
+                    .map(|name| make_path_conventional(&name.to_string_lossy()))
+            })
+            .unwrap_or_default()
+    }
+
+    fn schema(&self, uri: &str) -> Option<&Schema> {
+        let path = self.conventional(uri);
+        self.schemas
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&path))
+            .map(|(_, schema)| schema)
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) {
+        let diagnostics = match self.documents.get(uri) {
+            Some(text) => self.diagnostics(uri, text),
+            None => return,
+        };
+        self.outbox.push(notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        ));
+    }
+
+    fn diagnostics(&self, uri: &str, text: &str) -> Vec<Value> {
+        let index = LineIndex::new(text);
+        let diagnostic = |range: Value, severity: u32, message: &str| {
+            json!({
+                "range": range,
+                "severity": severity,
+                "source": "fformat",
+                "message": message,
+            })
+        };
+        let doc = match Document::parse(text) {
+            Ok(doc) => doc,
+            Err(err) => {
+                let line = error_line(&err).unwrap_or(1) - 1;
+                return vec![diagnostic(index.line_range(line), ERROR, err.trim_end())];
+            }
+        };
+        let mut diagnostics = Vec::new();
+        for section in &doc.sections {
+            let mut seen: Vec<&str> = Vec::new();
+            for (key, _, _) in section.entries() {
+                if seen.contains(&key) {
+                    let (start, end) = span(text, key);
+                    diagnostics.push(diagnostic(
+                        index.range(start, end),
+                        WARNING,
+                        &format!(
+                            "`{}` repeated in [{}], the last value wins",
+                            key,
+                            section.name()
+                        ),
+                    ));
+                } else {
+                    seen.push(key);
+                }
+            }
+        }
+        if let Some(Err(errors)) = self.schema(uri).map(|schema| schema.validate(&doc)) {
+            for err in errors {
+                let line = err.line.unwrap_or(1) - 1;
+                diagnostics.push(diagnostic(index.line_range(line), ERROR, &err.msg));
+            }
+        }
+        diagnostics
+    }
+
+    fn at_position(
+        &mut self,
+        params: &Value,
+        handler: fn(&mut Self, &str, &str, usize) -> Value,
+    ) -> Result<Value, Failure> {
+        let uri = text_document_uri(params)?;
+        let position = &params["position"];
+        let (line, character) = match (position["line"].as_u64(), position["character"].as_u64()) {
+            (Some(line), Some(character)) => (line as usize, character as usize),
+            _ => return Err((INVALID_PARAMS, "missing position".into())),
+        };
+        let text = match self.documents.get(uri) {
+            Some(text) => text.clone(),
+            None => return Ok(Value::Null),
+        };
+        let offset = LineIndex::new(&text).offset(line, character);
+        Ok(handler(self, uri, &text, offset))
+    }
+
+    fn key_schema(&self, uri: &str, section: &str, key: &str) -> Option<&KeySchema> {
+        let schema = self.schema(uri)?;
+        match section {
+            "" => schema.root.key(key),
+            section => schema.section(section)?.key(key),
+        }
+    }
+
+    fn hover(&mut self, uri: &str, text: &str, offset: usize) -> Value {
+        let doc = match Document::parse(text) {
+            Ok(doc) => doc,
+            Err(_) => return Value::Null,
+        };
+        let (section, line, on_key) = match entry_at(&doc, offset) {
+            Some(found) => found,
+            None => return Value::Null,
+        };
+        let (key, value) = line.entry().unwrap_or_default();
+        let mut contents = match section.name() {
+            "" => format!("**{}**", key),
+            name => format!("**{}** in `[{}]`", key, name),
+        };
+        if let Some(schema) = self.key_schema(uri, section.name(), key) {
+            contents.push_str(&format!("\n\n{}", describe(schema)));
+        }
+        if !on_key {
+            if let Some(native) = self.resolve_path(value) {
+                contents.push_str(&format!("\n\n`{}`", native.display()));
+            }
+            let definitions = self.definitions(uri, line.line - 1, value);
+            if let Some(first) = definitions.first() {
+                contents.push_str(&format!(
+                    "\n\ndefined in {} line {}",
+                    first["uri"].as_str().unwrap_or_default(),
+                    first["range"]["start"]["line"].as_u64().unwrap_or(0) + 1
+                ));
+            }
+        }
+        let (start, end) = span(text, if on_key { key } else { value });
+        json!({
+            "contents": { "kind": "markdown", "value": contents },
+            "range": LineIndex::new(text).range(start, end),
+        })
+    }
+
+    fn definition(&mut self, uri: &str, text: &str, offset: usize) -> Value {
+        let doc = match Document::parse(text) {
+            Ok(doc) => doc,
+            Err(_) => return Value::Null,
+        };
+        let (line, value) = match entry_at(&doc, offset) {
+            Some((_, line, false)) => (line.line - 1, line.entry().unwrap_or_default().1),
+            _ => return Value::Null,
+        };
+        if let Some(native) = self.resolve_path(value) {
+            return json!({
+                "uri": uri_from_path(&native),
+                "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+            });
+        }
+        Value::Array(self.definitions(uri, line, value))
+    }
+
+    /// Values with a separator or an extension are paths relative to the workspace root.
+    fn resolve_path(&mut self, value: &str) -> Option<PathBuf> {
+        let looks_like_path = value.contains(['/', '\\'])
+            || value.rsplit_once('.').is_some_and(|(stem, ext)| {
+                !stem.is_empty() && ext.chars().any(char::is_alphabetic)
+            });
+        if !looks_like_path || value.contains(char::is_whitespace) {
+            return None;
+        }
+        self.workspace.as_mut()?.resolve(value)
+    }
+
+    /// Locations of id entries with `value`, in open documents first and then in
+    /// workspace files of the same extension. `line` of `uri` itself is skipped.
+    fn definitions(&mut self, uri: &str, line: usize, value: &str) -> Vec<Value> {
+        if value.is_empty() {
+            return Vec::new();
+        }
+        let mut locations = Vec::new();
+        let mut push = |uri: &str, entry: &IdEntry| {
+            if entry.value == value {
+                locations.push(json!({ "uri": uri, "range": entry.range }));
+            }
+        };
+        for (doc_uri, text) in &self.documents {
+            let skip = (doc_uri == uri).then_some(line);
+            for entry in id_entries(text, &self.id_keys) {
+                if skip != Some(entry.line) {
+                    push(doc_uri, &entry);
+                }
+            }
+        }
+        let extension = Path::new(&self.conventional(uri))
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned());
+        let files = match (&mut self.workspace, extension) {
+            (Some(workspace), Some(extension)) => workspace.ids_with_extension(&extension),
+            _ => Vec::new(),
+        };
+        for (native, entries) in files {
+            let file_uri = uri_from_path(native);
+            if !self.documents.contains_key(&file_uri) {
+                for entry in entries {
+                    push(&file_uri, entry);
+                }
+            }
+        }
+        locations
+    }
+
+    fn completion(&mut self, uri: &str, text: &str, offset: usize) -> Value {
+        let index = LineIndex::new(text);
+        let (line, _) = index.position(offset);
+        let before = text[index.line_start(line)..offset].trim_start();
+        let doc = Document::parse(text).ok();
+        let schema = self.schema(uri);
+
+        if before.starts_with('[') {
+            let mut names: BTreeSet<&str> = schema
+                .map(|schema| {
+                    schema
+                        .sections
+                        .iter()
+                        .map(|section| section.name.as_str())
+                        .collect()
+                })
+                .unwrap_or_default();
+            names.extend(
+                doc.iter()
+                    .flat_map(|doc| doc.sections.iter().map(Section::name))
+                    .filter(|name| !name.is_empty()),
+            );
+            let items: Vec<Value> = names
+                .into_iter()
+                .map(|name| json!({ "label": name, "kind": COMPLETION_MODULE }))
+                .collect();
+            return Value::Array(items);
+        }
+
+        let doc = match doc {
+            Some(doc) => doc,
+            None => return Value::Array(Vec::new()),
+        };
+        let section = doc
+            .sections
+            .iter()
+            .rev()
+            .find(|section| section.header.is_none_or(|header| header.line <= line + 1))
+            .unwrap_or(&doc.sections[0]);
+        let section_schema = schema.and_then(|schema| match section.name() {
+            "" => Some(&schema.root),
+            name => schema.section(name),
+        });
+
+        if let Some(key_end) = before.find([' ', '\t', '=']) {
+            // after the key, offer the values of enums, flags and bools
+            let key = &before[..key_end];
+            let names = match section_schema
+                .and_then(|schema| schema.key(key))
+                .map(|key| &key.ty)
+            {
+                Some(Type::Enum(names)) | Some(Type::Flags(names)) => names.clone(),
+                Some(Type::Bool) => vec!["0".to_owned(), "1".to_owned()],
+                _ => Vec::new(),
+            };
+            let items: Vec<Value> = names
+                .into_iter()
+                .map(|name| json!({ "label": name, "kind": COMPLETION_ENUM_MEMBER }))
+                .collect();
+            return Value::Array(items);
+        }
+
+        let present: BTreeSet<&str> = section
+            .lines
+            .iter()
+            .filter(|entry| entry.line != line + 1)
+            .filter_map(|entry| entry.entry().map(|(key, _)| key))
+            .collect();
+        let mut items = Vec::new();
+        let mut offered = BTreeSet::new();
+        for key in section_schema.iter().flat_map(|schema| &schema.keys) {
+            if !present.contains(key.name.as_str()) && offered.insert(key.name.clone()) {
+                items.push(json!({
+                    "label": key.name,
+                    "kind": COMPLETION_PROPERTY,
+                    "detail": describe(key),
+                }));
+            }
+        }
+        let mut seen = BTreeSet::new();
+        for text in self.documents.values() {
+            if let Ok(other) = Document::parse(text) {
+                for other in other
+                    .sections
+                    .iter()
+                    .filter(|other| other.name() == section.name())
+                {
+                    seen.extend(other.entries().map(|(key, _, _)| key.to_owned()));
+                }
+            }
+        }
+        for key in seen {
+            if !present.contains(key.as_str()) && offered.insert(key.clone()) {
+                items.push(json!({ "label": key, "kind": COMPLETION_PROPERTY }));
+            }
+        }
+        Value::Array(items)
+    }
+}
+
+fn text_document_uri(params: &Value) -> Result<&str, Failure> {
+    params["textDocument"]["uri"]
+        .as_str()
+        .ok_or_else(|| (INVALID_PARAMS, "missing textDocument.uri".into()))
+}
+
+/// The entry line under `offset` and its section, `true` when on the key.
+fn entry_at<'d, 'a>(
+    doc: &'d Document<'a>,
+    offset: usize,
+) -> Option<(&'d Section<'a>, &'d Line<'a>, bool)> {
+    doc.sections.iter().find_map(|section| {
+        section.lines.iter().find_map(|line| {
+            let (key, _) = line.entry()?;
+            let key_end = key.as_ptr() as usize - line.raw.as_ptr() as usize + key.len();
+            let within = offset.checked_sub(line.offset)?;
+            (within <= line.raw.len()).then_some((section, line, within <= key_end))
+        })
+    })
+}
+
+/// `int`, required, 1..=10, default 5
+fn describe(key: &KeySchema) -> String {
+    let mut parts = vec![format!("`{}`", key.ty)];
+    if key.required {
+        parts.push("required".to_owned());
+    }
+    if key.min.is_some() || key.max.is_some() {
+        let bound = |bound: Option<f64>| bound.map(|bound| bound.to_string()).unwrap_or_default();
+        parts.push(format!("{}..={}", bound(key.min), bound(key.max)));
+    }
+    if let Some(len) = key.len {
+        parts.push(format!("{} items", len));
+    }
+    if let Some(default) = &key.default {
+        parts.push(format!("default {:?}", default));
+    }
+    parts.join(", ")
+}
+
+fn document_symbols(text: &str) -> Value {
+    let doc = match Document::parse(text) {
+        Ok(doc) => doc,
+        Err(_) => return Value::Null,
+    };
+    let index = LineIndex::new(text);
+    let entry_symbol = |line: &Line| {
+        let (key, value) = line.entry()?;
+        let (start, end) = span(text, key);
+        Some(json!({
+            "name": key,
+            "detail": value,
+            "kind": SYMBOL_PROPERTY,
+            "range": index.range(line.offset, line.offset + line.raw.len()),
+            "selectionRange": index.range(start, end),
+        }))
+    };
+    let mut symbols = Vec::new();
+    for section in &doc.sections {
+        let children: Vec<Value> = section.lines.iter().filter_map(entry_symbol).collect();
+        let header = match &section.header {
+            Some(header) => header,
+            None => {
+                symbols.extend(children);
+                continue;
+            }
+        };
+        let last = section.lines.last().unwrap_or(header);
+        let (start, end) = span(text, section.name());
+        symbols.push(json!({
+            "name": section.name(),
+            "kind": SYMBOL_NAMESPACE,
+            "range": index.range(header.offset, last.offset + last.raw.len()),
+            "selectionRange": index.range(start, end),
+            "children": children,
+        }));
+    }
+    Value::Array(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor};
+
+    use super::*;
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn position(uri: &str, line: u32, character: u32) -> Value {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+        })
+    }
+
+    fn open(uri: &str, text: &str) -> Value {
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": uri, "languageId": "fformat", "version": 1, "text": text } }),
+        )
+    }
+
+    /// Removed on drop, also when an assertion fails.
+    struct TempRoot(PathBuf);
+
+    impl TempRoot {
+        fn new(name: &str) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("fformat_lsp_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            TempRoot(root)
+        }
+    }
+
+    impl std::ops::Deref for TempRoot {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_stdio() {
+        let mut input = Vec::new();
+        for message in [
+            request(1, "initialize", json!({ "rootUri": null })),
+            notification("initialized", json!({})),
+            open("file:///a.fopro", "[Proto]\nPid = 1\nPid = 2\n[Broken\n"),
+            request(
+                2,
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": "file:///a.fopro" } }),
+            ),
+            request(3, "nope", json!({})),
+            request(4, "shutdown", Value::Null),
+            notification("exit", Value::Null),
+        ] {
+            write_message(&mut input, &message).unwrap();
+        }
+        let mut output = Vec::new();
+        let code = Server::new().run(Cursor::new(input), &mut output).unwrap();
+        assert_eq!(code, 0);
+
+        let mut output = Cursor::new(output);
+        let mut replies = Vec::new();
+        while let Some(body) = read_message(&mut output).unwrap() {
+            replies.push(serde_json::from_slice::<Value>(&body).unwrap());
+        }
+        assert_eq!(replies.len(), 5);
+        assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+        let diagnostics = &replies[1]["params"]["diagnostics"];
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 3);
+        assert_eq!(replies[2]["result"], Value::Null);
+        assert_eq!(replies[3]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[4]["id"], 4);
+    }
+
+    #[test]
+    fn test_features() {
+        let root = TempRoot::new("features");
+        fs::create_dir_all(root.join("Art/Items")).unwrap();
+        fs::create_dir_all(root.join("proto")).unwrap();
+        fs::write(root.join("Art/Items/Gun.frm"), "").unwrap();
+        fs::write(root.join("proto/ammo.fopro"), "[Proto]\nPid = 7\n").unwrap();
+        fs::write(
+            root.join("proto.schema"),
+            "[Proto]\n@multiple = 1\nPid = int required\nType = enum(Item | Critter) default=Item\nPicMap = string\nAmmoPid = int\n",
+        )
+        .unwrap();
+
+        let mut server = Server::new();
+        server.handle(&request(
+            1,
+            "initialize",
+            json!({
+                "rootUri": uri_from_path(&root),
+                "initializationOptions": { "schemas": { "proto/*.fopro": "proto.schema" } },
+            }),
+        ));
+        let uri = uri_from_path(&root.join("proto/gun.fopro"));
+        let text = "[Proto]\nPid = 5\nPicMap = art\\items\\gun.frm\nAmmoPid = 7\nType = Thing\n\n";
+        let replies = server.handle(&open(&uri, text));
+        let diagnostics = replies[0]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 4);
+
+        let hover = &server.handle(&request(2, "textDocument/hover", position(&uri, 1, 1)))[0];
+        let contents = hover["result"]["contents"]["value"].as_str().unwrap();
+        assert!(contents.contains("**Pid** in `[Proto]`"), "{}", contents);
+        assert!(contents.contains("`int`, required"), "{}", contents);
+
+        let path = &server.handle(&request(
+            3,
+            "textDocument/definition",
+            position(&uri, 2, 12),
+        ))[0];
+        assert_eq!(
+            path["result"]["uri"],
+            uri_from_path(&root.join("Art/Items/Gun.frm"))
+        );
+        let id = &server.handle(&request(
+            4,
+            "textDocument/definition",
+            position(&uri, 3, 11),
+        ))[0];
+        assert_eq!(
+            id["result"][0]["uri"],
+            uri_from_path(&root.join("proto/ammo.fopro"))
+        );
+        assert_eq!(id["result"][0]["range"]["start"]["line"], 1);
+        // parsed once, read again after the editor reports a change
+        fs::write(root.join("proto/ammo.fopro"), "[Proto]\nPid = 8\n").unwrap();
+        let cached = &server.handle(&request(
+            4,
+            "textDocument/definition",
+            position(&uri, 3, 11),
+        ))[0];
+        assert_eq!(cached["result"].as_array().unwrap().len(), 1);
+        server.handle(&notification(
+            "workspace/didChangeWatchedFiles",
+            json!({ "changes": [] }),
+        ));
+        let changed = &server.handle(&request(
+            4,
+            "textDocument/definition",
+            position(&uri, 3, 11),
+        ))[0];
+        assert!(changed["result"].as_array().unwrap().is_empty());
+
+        let new = uri_from_path(&root.join("proto/new.fopro"));
+        server.handle(&open(&new, "[Proto]\nPid = 1\n\n"));
+        let keys = &server.handle(&request(5, "textDocument/completion", position(&new, 2, 0)))[0];
+        let labels: Vec<_> = keys["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap())
+            .collect();
+        assert_eq!(labels, ["Type", "PicMap", "AmmoPid"]);
+        let values =
+            &server.handle(&request(6, "textDocument/completion", position(&uri, 4, 7)))[0];
+        assert_eq!(values["result"][1]["label"], "Critter");
+
+        let symbols = &server.handle(&request(
+            7,
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": uri } }),
+        ))[0];
+        assert_eq!(symbols["result"][0]["name"], "Proto");
+        assert_eq!(
+            symbols["result"][0]["children"].as_array().unwrap().len(),
+            4
+        );
+    }
+}
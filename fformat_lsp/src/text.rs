@@ -0,0 +1,115 @@
+use serde_json::{json, Value};
+
+/// Maps byte offsets to LSP positions, which count UTF-16 code units. Lines end at
+/// `\n`, `\r\n` or a bare `\r`, like in the parsers.
+pub struct LineIndex<'t> {
+    text: &'t str,
+    starts: Vec<usize>,
+}
+
+impl<'t> LineIndex<'t> {
+    pub fn new(text: &'t str) -> Self {
+        let bytes = text.as_bytes();
+        let mut starts = vec![0];
+        let mut i = 0;
+        while i < bytes.len() {
+            i += match (bytes[i], bytes.get(i + 1)) {
+                (b'\r', Some(b'\n')) => 2,
+                (b'\r', _) | (b'\n', _) => 1,
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            starts.push(i);
+        }
+        LineIndex { text, starts }
+    }
+
+    /// Byte offset of the start of a 0-based line.
+    pub fn line_start(&self, line: usize) -> usize {
+        self.starts[line.min(self.starts.len() - 1)]
+    }
+
+    /// A 0-based line without its ending.
+    pub fn line_text(&self, line: usize) -> &'t str {
+        let line = line.min(self.starts.len() - 1);
+        let end = self
+            .starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        self.text[self.starts[line]..end].trim_end_matches(['\r', '\n'])
+    }
+
+    /// 0-based line and UTF-16 column.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.text[self.starts[line]..offset].encode_utf16().count();
+        (line, column)
+    }
+
+    /// Byte offset of an LSP position, clamped to the end of its line.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let start = self.line_start(line);
+        let text = self.line_text(line);
+        let mut units = 0;
+        for (index, ch) in text.char_indices() {
+            if units >= character {
+                return start + index;
+            }
+            units += ch.len_utf16();
+        }
+        start + text.len()
+    }
+
+    pub fn range(&self, start: usize, end: usize) -> Value {
+        let (start, end) = (self.position(start), self.position(end));
+        json!({
+            "start": { "line": start.0, "character": start.1 },
+            "end": { "line": end.0, "character": end.1 },
+        })
+    }
+
+    /// A whole 0-based line.
+    pub fn line_range(&self, line: usize) -> Value {
+        let start = self.line_start(line);
+        self.range(start, start + self.line_text(line).len())
+    }
+}
+
+/// Byte range of `part`, a slice of `text`.
+pub fn span(text: &str, part: &str) -> (usize, usize) {
+    let start = part.as_ptr() as usize - text.as_ptr() as usize;
+    (start, start + part.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let text = "[Имя]\r\nA = 😀x\rB\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_text(0), "[Имя]");
+        assert_eq!(index.line_text(1), "A = 😀x");
+        assert_eq!(index.line_text(2), "B");
+        assert_eq!(index.line_text(3), "");
+        let x = text.find('x').unwrap();
+        assert_eq!(index.position(x), (1, 6));
+        assert_eq!(index.offset(1, 6), x);
+        assert_eq!(index.offset(1, 100), x + 1);
+        assert_eq!(index.position(text.len()), (3, 0));
+        assert_eq!(
+            index.line_range(0),
+            json!({
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 5 },
+            })
+        );
+        let (start, end) = span(text, &text[x..x + 1]);
+        assert_eq!((start, end), (x, x + 1));
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use fformat_utils::{
+    batch::{DirSource, Source},
+    cp1251, make_path_conventional,
+    native::{conventional_from_native_lossy, NativeResolver},
+};
+use nom_prelude::document::Document;
+use serde_json::Value;
+
+use crate::text::{span, LineIndex};
+
+/// `file://` URI to a native path, percent escapes decoded.
+pub fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => encoded
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // `/C:/data` on Windows
+    let path = match path.strip_prefix('/') {
+        Some(rest) if cfg!(windows) && rest.get(1..2) == Some(":") => rest.to_owned(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+pub fn uri_from_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// An entry whose key is one of the id keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdEntry {
+    pub value: String,
+    /// 0-based.
+    pub line: usize,
+    /// LSP range of the value.
+    pub range: Value,
+}
+
+/// Entries of `text` keyed by one of `id_keys`, none if it doesn't parse.
+pub fn id_entries(text: &str, id_keys: &[String]) -> Vec<IdEntry> {
+    let doc = match Document::parse(text) {
+        Ok(doc) => doc,
+        Err(_) => return Vec::new(),
+    };
+    let index = LineIndex::new(text);
+    doc.lines()
+        .filter_map(|line| {
+            let (key, value) = line.entry()?;
+            id_keys.iter().any(|id| id == key).then(|| {
+                let (start, end) = span(text, value);
+                IdEntry {
+                    value: value.to_owned(),
+                    line: line.line - 1,
+                    range: index.range(start, end),
+                }
+            })
+        })
+        .collect()
+}
+
+/// The data folder the editor opened, files are addressed by conventional path
+/// relative to it.
+pub struct Workspace {
+    resolver: NativeResolver,
+    id_keys: Vec<String>,
+    // listing and id entries by native path for id lookups, dropped on changes
+    files: Option<DirSource>,
+    ids: BTreeMap<PathBuf, Vec<IdEntry>>,
+}
+
+impl Workspace {
+    pub fn new<P: Into<PathBuf>>(root: P, id_keys: Vec<String>) -> Self {
+        Workspace {
+            resolver: NativeResolver::new(root),
+            id_keys,
+            files: None,
+            ids: BTreeMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        self.resolver.root()
+    }
+
+    pub fn conventional(&self, path: &Path) -> Option<String> {
+        conventional_from_native_lossy(self.root(), path)
+            .ok()
+            .map(|path| path.into_string())
+    }
+
+    /// Native file a path written in some value refers to.
+    pub fn resolve(&mut self, path: &str) -> Option<PathBuf> {
+        let path = make_path_conventional(path);
+        if path.is_empty() {
+            return None;
+        }
+        self.resolver
+            .resolve(&path)
+            .ok()
+            .filter(|path| path.is_file())
+    }
+
+    /// Id entries of every file with `extension` by native path. Each file is read
+    /// and parsed once until [`Workspace::invalidate`].
+    pub fn ids_with_extension(&mut self, extension: &str) -> Vec<(&Path, &[IdEntry])> {
+        if self.files.is_none() {
+            self.files = DirSource::open(self.root()).ok();
+        }
+        let files = match &self.files {
+            Some(files) => files,
+            None => return Vec::new(),
+        };
+        let suffix = format!(".{}", make_path_conventional(extension));
+        let mut natives = Vec::new();
+        for path in files.paths() {
+            let native = match files.native(&path) {
+                Some(native) if path.ends_with(&suffix) => native.to_owned(),
+                _ => continue,
+            };
+            if !self.ids.contains_key(&native) {
+                let bytes = match files.read(&path) {
+                    Ok(bytes) => bytes,
+                    Err(_) => continue,
+                };
+                let text = match String::from_utf8(bytes) {
+                    Ok(text) => text,
+                    Err(err) => cp1251::decode(err.as_bytes()),
+                };
+                self.ids
+                    .insert(native.clone(), id_entries(&text, &self.id_keys));
+            }
+            natives.push(native);
+        }
+        let ids = &self.ids;
+        natives
+            .into_iter()
+            .filter_map(|native| ids.get_key_value(&native))
+            .map(|(native, entries)| (native.as_path(), entries.as_slice()))
+            .collect()
+    }
+
+    /// Forgets cached listings after files changed on disk.
+    pub fn invalidate(&mut self) {
+        self.resolver.clear();
+        self.files = None;
+        self.ids.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri() {
+        let path = Path::new("/data/Мой мод/proto#1.fopro");
+        let uri = uri_from_path(path);
+        assert_eq!(
+            uri,
+            "file:///data/%D0%9C%D0%BE%D0%B9%20%D0%BC%D0%BE%D0%B4/proto%231.fopro"
+        );
+        assert_eq!(path_from_uri(&uri).as_deref(), Some(path));
+        assert_eq!(path_from_uri("untitled:1"), None);
+    }
+}
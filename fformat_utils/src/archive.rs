@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::Path,
+};
+
+pub use zip::result::{ZipError, ZipResult};
+use zip::{read::ZipFile, ZipArchive};
+
+use crate::CaseFolding;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub path: String,
+    pub kept: String,
+    pub shadowed: String,
+}
+
+pub struct ZipPack<R> {
+    archive: ZipArchive<R>,
+    folding: CaseFolding,
+    index: HashMap<String, usize>,
+    collisions: Vec<Collision>,
+}
+
+impl ZipPack<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> ZipResult<Self> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> ZipPack<R> {
+    pub fn new(reader: R) -> ZipResult<Self> {
+        Self::with_folding(reader, CaseFolding::default())
+    }
+
+    pub fn with_folding(reader: R, folding: CaseFolding) -> ZipResult<Self> {
+        let mut archive = ZipArchive::new(reader)?;
+        let mut index: HashMap<String, usize> = HashMap::with_capacity(archive.len());
+        let mut collisions = Vec::new();
+        let mut raw_names: Vec<Option<String>> = vec![None; archive.len()];
+
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let raw = file.name().to_owned();
+            let path = folding.make_path_conventional(&raw);
+            if path.is_empty() {
+                continue;
+            }
+            match index.get(&path) {
+                Some(&kept) => collisions.push(Collision {
+                    path,
+                    kept: raw_names[kept].clone().unwrap_or_default(),
+                    shadowed: raw,
+                }),
+                None => {
+                    index.insert(path, i);
+                    raw_names[i] = Some(raw);
+                }
+            }
+        }
+
+        Ok(ZipPack {
+            archive,
+            folding,
+            index,
+            collisions,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// In archive order.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        let mut paths: Vec<(&String, &usize)> = self.index.iter().collect();
+        paths.sort_unstable_by_key(|&(_, &index)| index);
+        paths.into_iter().map(|(path, _)| path.as_str())
+    }
+
+    /// `path` may be in any case and use either separator.
+    pub fn contains(&self, path: &str) -> bool {
+        self.index_of(path).is_some()
+    }
+
+    /// Only the conventional `path` itself, see [`crate::PathIndex::contains_path`].
+    pub(crate) fn contains_conventional(&self, path: &str) -> bool {
+        self.index.contains_key(path)
+    }
+
+    pub fn size(&mut self, path: &str) -> ZipResult<u64> {
+        Ok(self.entry(path)?.size())
+    }
+
+    /// Streams the decompressed entry without buffering it whole.
+    pub fn entry(&mut self, path: &str) -> ZipResult<ZipFile<'_>> {
+        let index = self.index_of(path).ok_or(ZipError::FileNotFound)?;
+        self.archive.by_index(index)
+    }
+
+    pub fn read(&mut self, path: &str) -> ZipResult<Vec<u8>> {
+        let mut entry = self.entry(path)?;
+        // the header's size may lie, let a larger entry grow the buffer
+        let mut buf = Vec::with_capacity(entry.size().min(1 << 20) as usize);
+        entry.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.archive.into_inner()
+    }
+
+    fn index_of(&self, path: &str) -> Option<usize> {
+        match self.index.get(path) {
+            Some(&index) => Some(index),
+            None => self
+                .index
+                .get(&self.folding.make_path_conventional(path))
+                .copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    use super::*;
+
+    pub(crate) fn make_zip(entries: &[(&str, &[u8])]) -> Cursor<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let mut cursor = writer.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn test_lookup_conventional() {
+        let zip = make_zip(&[
+            ("text/engl/GAME.msg", b"msg"),
+            ("Art\\Items\\GUN.FRM", b"gun"),
+        ]);
+        let mut pack = ZipPack::new(zip).unwrap();
+        assert_eq!(pack.len(), 2);
+        assert_eq!(
+            pack.paths().collect::<Vec<_>>(),
+            ["text/engl/game.msg", "art/items/gun.frm"]
+        );
+        assert!(pack.contains("art/items/gun.frm"));
+        assert!(pack.contains("ART\\items\\Gun.frm"));
+        assert_eq!(pack.read("text/engl/game.msg").unwrap(), b"msg");
+        assert!(matches!(
+            pack.read("art/items/missing.frm"),
+            Err(ZipError::FileNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_collisions() {
+        let zip = make_zip(&[("art/a.frm", b"first"), ("ART\\A.FRM", b"second")]);
+        let mut pack = ZipPack::new(zip).unwrap();
+        assert_eq!(
+            pack.collisions(),
+            &[Collision {
+                path: "art/a.frm".into(),
+                kept: "art/a.frm".into(),
+                shadowed: "ART\\A.FRM".into(),
+            }]
+        );
+        assert_eq!(pack.read("art/a.frm").unwrap(), b"first");
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, Read, Seek},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    archive::ZipPack,
+    glob::{Pattern, PatternError},
+    native::{conventional_from_native_lossy, NativePathError},
+};
+
+/// Files a batch can read, addressed by conventional path.
+pub trait Source: Sync {
+    fn paths(&self) -> Vec<String>;
+    fn read(&self, path: &str) -> io::Result<Vec<u8>>;
+}
+
+/// A data folder on disk.
+#[derive(Debug, Clone)]
+pub struct DirSource {
+    root: PathBuf,
+    // names differing only in case share a path
+    files: BTreeMap<String, Vec<PathBuf>>,
+}
+
+impl DirSource {
+    /// Lists every file below `root`. Names differing only in case are
+    /// [`DirSource::ambiguous`], reading them fails.
+    pub fn open<P: Into<PathBuf>>(root: P) -> io::Result<Self> {
+        let root = root.into();
+        let mut source = DirSource {
+            files: BTreeMap::new(),
+            root,
+        };
+        let root = source.root.clone();
+        source.scan(&root)?;
+        Ok(source)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `None` for ambiguous paths too.
+    pub fn native(&self, path: &str) -> Option<&Path> {
+        match self.files.get(path).map(Vec::as_slice) {
+            Some([native]) => Some(native),
+            _ => None,
+        }
+    }
+
+    /// Paths with several native files, which are in byte order.
+    pub fn ambiguous(&self) -> impl Iterator<Item = (&str, &[PathBuf])> {
+        self.files
+            .iter()
+            .filter(|(_, natives)| natives.len() > 1)
+            .map(|(path, natives)| (path.as_str(), natives.as_slice()))
+    }
+
+    fn scan(&mut self, dir: &Path) -> io::Result<()> {
+        let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let native = entry.path();
+            if entry.file_type()?.is_dir() {
+                self.scan(&native)?;
+            } else if let Ok(path) = conventional_from_native_lossy(&self.root, &native) {
+                self.files
+                    .entry(path.into_string())
+                    .or_default()
+                    .push(native);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Source for DirSource {
+    fn paths(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        match self.files.get(path).map(Vec::as_slice) {
+            Some([native]) => fs::read(native),
+            Some(natives) if !natives.is_empty() => {
+                Err(io::Error::other(NativePathError::Ambiguous {
+                    path: path.to_owned(),
+                    candidates: natives.to_vec(),
+                }))
+            }
+            _ => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+/// Reads are serialized, parsing still runs in parallel.
+impl<R: Read + Seek + Send> Source for Mutex<ZipPack<R>> {
+    fn paths(&self) -> Vec<String> {
+        let pack = self.lock().unwrap_or_else(|err| err.into_inner());
+        pack.paths().map(str::to_owned).collect()
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let mut pack = self.lock().unwrap_or_else(|err| err.into_inner());
+        pack.read(path).map_err(io::Error::from)
+    }
+}
+
+type Parser<'f, T> = Box<dyn Fn(&str, &[u8]) -> Result<T, String> + Sync + 'f>;
+
+/// Maps paths to parsers by glob, the first matching rule wins.
+pub struct FileTypes<'f, T> {
+    rules: Vec<(String, Pattern, Parser<'f, T>)>,
+}
+
+impl<'f, T> Default for FileTypes<'f, T> {
+    fn default() -> Self {
+        FileTypes { rules: Vec::new() }
+    }
+}
+
+impl<'f, T> FileTypes<'f, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `parser` gets the conventional path and the file contents.
+    pub fn add<F>(&mut self, name: &str, pattern: &str, parser: F) -> Result<(), PatternError>
+    where
+        F: Fn(&str, &[u8]) -> Result<T, String> + Sync + 'f,
+    {
+        self.rules
+            .push((name.to_owned(), Pattern::new(pattern)?, Box::new(parser)));
+        Ok(())
+    }
+
+    /// Index of the rule for `path`.
+    fn rule(&self, path: &str) -> Option<usize> {
+        self.rules
+            .iter()
+            .position(|(_, pattern, _)| pattern.matches(path))
+    }
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Io(err) => write!(f, "read failed: {}", err),
+            FileError::Parse(err) => f.write_str(err),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+#[derive(Debug)]
+pub struct FileResult<T> {
+    pub path: String,
+    /// Name of the matching [`FileTypes`] rule.
+    pub kind: String,
+    pub size: usize,
+    pub result: Result<T, FileError>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    pub files: usize,
+    pub failed: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub files: usize,
+    pub failed: usize,
+    /// Files no rule matched.
+    pub skipped: usize,
+    pub bytes: u64,
+    pub per_type: BTreeMap<String, TypeStats>,
+}
+
+#[derive(Debug)]
+pub struct Report<T> {
+    /// Sorted by path.
+    pub results: Vec<FileResult<T>>,
+    pub stats: Stats,
+}
+
+impl<T> Report<T> {
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &FileError)> {
+        self.results.iter().filter_map(|file| {
+            file.result
+                .as_ref()
+                .err()
+                .map(|err| (file.path.as_str(), err))
+        })
+    }
+}
+
+/// Parses every file of `source` some rule of `types` matches on `threads` workers,
+/// `0` meaning one per core. The report does not depend on the thread count.
+pub fn parse_all<S, T>(source: &S, types: &FileTypes<'_, T>, threads: usize) -> Report<T>
+where
+    S: Source + ?Sized,
+    T: Send,
+{
+    let mut paths = source.paths();
+    paths.sort_unstable();
+    paths.dedup();
+    let total = paths.len();
+    let jobs: Vec<(String, usize)> = paths
+        .into_iter()
+        .filter_map(|path| types.rule(&path).map(|rule| (path, rule)))
+        .collect();
+    let skipped = total - jobs.len();
+
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .clamp(1, jobs.len().max(1));
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<FileResult<T>>>> = jobs.iter().map(|_| Mutex::new(None)).collect();
+
+    let work = || loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        let (path, rule) = match jobs.get(index) {
+            Some(job) => job,
+            None => break,
+        };
+        let (name, _, parser) = &types.rules[*rule];
+        let (size, result) = match source.read(path) {
+            Ok(bytes) => (bytes.len(), parser(path, &bytes).map_err(FileError::Parse)),
+            Err(err) => (0, Err(FileError::Io(err))),
+        };
+        *slots[index].lock().unwrap() = Some(FileResult {
+            path: path.clone(),
+            kind: name.clone(),
+            size,
+            result,
+        });
+    };
+    if threads == 1 {
+        work();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(work);
+            }
+        });
+    }
+
+    let results: Vec<FileResult<T>> = slots
+        .into_iter()
+        .map(|slot| slot.into_inner().unwrap().expect("every job ran"))
+        .collect();
+    let mut stats = Stats {
+        skipped,
+        ..Stats::default()
+    };
+    for file in &results {
+        let failed = file.result.is_err() as usize;
+        let per_type = stats.per_type.entry(file.kind.clone()).or_default();
+        per_type.files += 1;
+        per_type.failed += failed;
+        per_type.bytes += file.size as u64;
+        stats.files += 1;
+        stats.failed += failed;
+        stats.bytes += file.size as u64;
+    }
+    Report { results, stats }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::tests::make_zip;
+
+    fn types() -> FileTypes<'static, usize> {
+        let mut types = FileTypes::new();
+        types
+            .add("proto", "proto/**/*.fopro", |_, bytes: &[u8]| {
+                std::str::from_utf8(bytes)
+                    .map_err(|err| err.to_string())
+                    .map(|text| text.lines().count())
+            })
+            .unwrap();
+        types
+            .add("msg", "text/*/*.msg", |path, bytes: &[u8]| {
+                if bytes.starts_with(b"{") {
+                    Ok(bytes.iter().filter(|&&byte| byte == b'{').count() / 3)
+                } else {
+                    Err(format!("{}: expected `{{`", path))
+                }
+            })
+            .unwrap();
+        types
+    }
+
+    #[test]
+    fn test_parse_zip() {
+        let zip = make_zip(&[
+            ("Proto/Items/Gun.fopro", b"[Proto]\nPid = 1\n".as_slice()),
+            ("proto/critters/rat.fopro", b"[Proto]\n".as_slice()),
+            (
+                "TEXT/ENGL/GAME.MSG",
+                b"{100}{}{Hello}\n{101}{}{Bye}\n".as_slice(),
+            ),
+            ("text/russ/game.msg", b"oops".as_slice()),
+            ("art/gun.frm", b"\0\0".as_slice()),
+        ]);
+        let source = Mutex::new(ZipPack::new(zip).unwrap());
+        let types = types();
+        let report = parse_all(&source, &types, 1);
+        let summary: Vec<(&str, &str, Option<usize>)> = report
+            .results
+            .iter()
+            .map(|file| {
+                (
+                    file.path.as_str(),
+                    file.kind.as_str(),
+                    file.result.as_ref().ok().copied(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("proto/critters/rat.fopro", "proto", Some(1)),
+                ("proto/items/gun.fopro", "proto", Some(2)),
+                ("text/engl/game.msg", "msg", Some(2)),
+                ("text/russ/game.msg", "msg", None),
+            ]
+        );
+        assert_eq!(report.stats.files, 4);
+        assert_eq!(report.stats.failed, 1);
+        assert_eq!(report.stats.skipped, 1);
+        assert_eq!(
+            report.stats.per_type["msg"],
+            TypeStats {
+                files: 2,
+                failed: 1,
+                bytes: 32
+            }
+        );
+        let failures: Vec<_> = report.failures().map(|(path, _)| path).collect();
+        assert_eq!(failures, ["text/russ/game.msg"]);
+
+        for threads in [0, 2, 8] {
+            let other = parse_all(&source, &types, threads);
+            assert_eq!(other.stats, report.stats);
+            let paths: Vec<_> = other.results.iter().map(|file| &file.path).collect();
+            let expected: Vec<_> = report.results.iter().map(|file| &file.path).collect();
+            assert_eq!(paths, expected);
+        }
+    }
+
+    #[test]
+    fn test_dir_source() {
+        let root = std::env::temp_dir().join(format!("fformat_utils_batch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Proto/Items")).unwrap();
+        fs::write(root.join("Proto/Items/Gun.fopro"), "[Proto]\n").unwrap();
+        fs::write(root.join("readme.txt"), "").unwrap();
+        fs::write(root.join("Proto/Items/KNIFE.fopro"), "[Proto]\n").unwrap();
+        fs::write(root.join("Proto/Items/knife.fopro"), "[Proto]\n").unwrap();
+
+        let source = DirSource::open(&root).unwrap();
+        assert_eq!(
+            source.paths(),
+            [
+                "proto/items/gun.fopro",
+                "proto/items/knife.fopro",
+                "readme.txt"
+            ]
+        );
+        let knife = [
+            root.join("Proto/Items/KNIFE.fopro"),
+            root.join("Proto/Items/knife.fopro"),
+        ];
+        assert_eq!(
+            source.ambiguous().collect::<Vec<_>>(),
+            [("proto/items/knife.fopro", &knife[..])]
+        );
+        assert_eq!(source.native("proto/items/knife.fopro"), None);
+        assert_eq!(
+            source.native("proto/items/gun.fopro"),
+            Some(root.join("Proto/Items/Gun.fopro").as_path())
+        );
+        let report = parse_all(&source, &types(), 4);
+        assert_eq!(report.stats.files, 2);
+        assert_eq!(report.stats.skipped, 1);
+        assert_eq!(report.results[0].result.as_ref().ok(), Some(&1));
+        let failures: Vec<_> = report.failures().map(|(path, _)| path).collect();
+        assert_eq!(failures, ["proto/items/knife.fopro"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,58 @@
+// 0x80..=0xBF, 0x98 is unassigned
+#[rustfmt::skip]
+const HIGH: [char; 64] = [
+    'Ђ', 'Ѓ', '‚', 'ѓ', '„', '…', '†', '‡', '€', '‰', 'Љ', '‹', 'Њ', 'Ќ', 'Ћ', 'Џ',
+    'ђ', '‘', '’', '“', '”', '•', '–', '—', '\u{FFFD}', '™', 'љ', '›', 'њ', 'ќ', 'ћ', 'џ',
+    '\u{A0}', 'Ў', 'ў', 'Ј', '¤', 'Ґ', '¦', '§', 'Ё', '©', 'Є', '«', '¬', '\u{AD}', '®', 'Ї',
+    '°', '±', 'І', 'і', 'ґ', 'µ', '¶', '·', 'ё', '№', 'є', '»', 'ј', 'Ѕ', 'ѕ', 'ї',
+];
+
+pub fn decode_byte(byte: u8) -> char {
+    match byte {
+        0..=0x7F => byte as char,
+        0x80..=0xBF => HIGH[byte as usize - 0x80],
+        // А-я
+        _ => char::from_u32(0x0410 + (byte - 0xC0) as u32).unwrap_or('\u{FFFD}'),
+    }
+}
+
+pub fn encode_char(ch: char) -> Option<u8> {
+    match ch as u32 {
+        code @ 0..=0x7F => Some(code as u8),
+        code @ 0x0410..=0x044F => Some((code - 0x0410) as u8 + 0xC0),
+        _ if ch == '\u{FFFD}' => None,
+        _ => HIGH
+            .iter()
+            .position(|&high| high == ch)
+            .map(|pos| pos as u8 + 0x80),
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| decode_byte(byte)).collect()
+}
+
+/// Unmappable chars become `?`, like `WideCharToMultiByte` does.
+pub fn encode_lossy(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|ch| encode_char(ch).unwrap_or(b'?'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        for byte in 0..=255u8 {
+            if byte == 0x98 {
+                assert_eq!(decode_byte(byte), '\u{FFFD}');
+                continue;
+            }
+            assert_eq!(encode_char(decode_byte(byte)), Some(byte));
+        }
+        assert_eq!(decode(b"\xC4\xE8\xE0\xEB\xEE\xE3 \xA8\xB8"), "Диалог Ёё");
+        assert_eq!(encode_lossy("ж✓"), b"\xE6?");
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use crate::{CaseFolding, PathIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntryKind {
+    File,
+    Dir,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DirEntry<'a> {
+    pub name: &'a str,
+    pub path: &'a str,
+    pub kind: EntryKind,
+}
+
+/// Directory view over a sorted list of conventional paths. Every method takes raw
+/// paths and makes them conventional with the index's [`CaseFolding`].
+#[derive(Debug, Clone, Default)]
+pub struct DirIndex {
+    folding: CaseFolding,
+    paths: Vec<String>,
+}
+
+impl DirIndex {
+    /// `paths` are made conventional, duplicates are dropped.
+    pub fn new<I, S>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::with_folding(paths, CaseFolding::default())
+    }
+
+    pub fn with_folding<I, S>(paths: I, folding: CaseFolding) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut paths: Vec<String> = paths
+            .into_iter()
+            .map(|path| folding.make_path_conventional(path.as_ref()))
+            .filter(|path| !path.is_empty())
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        DirIndex { folding, paths }
+    }
+
+    /// Keeps the folding of `index`.
+    pub fn from_index<I: PathIndex + ?Sized>(index: &I) -> Self {
+        Self::with_folding(index.paths(), index.folding())
+    }
+
+    pub fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn insert(&mut self, path: &str) -> bool {
+        let path = self.folding.make_path_conventional(path);
+        match self.paths.binary_search(&path) {
+            Ok(_) => false,
+            Err(pos) => {
+                self.paths.insert(pos, path);
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, path: &str) -> bool {
+        let path = self.folding.make_path_conventional(path);
+        match self.paths.binary_search(&path) {
+            Ok(pos) => {
+                self.paths.remove(pos);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.contains_conventional(&self.folding.make_path_conventional(path))
+    }
+
+    fn contains_conventional(&self, path: &str) -> bool {
+        self.paths
+            .binary_search_by(|item| item.as_str().cmp(path))
+            .is_ok()
+    }
+
+    pub fn is_dir(&self, dir: &str) -> bool {
+        let dir = self.folding.make_path_conventional(dir);
+        dir.is_empty() || self.walk(&dir).next().is_some()
+    }
+
+    /// All paths starting with `prefix` as a plain string, not component-wise. Only
+    /// case and separators of `prefix` are made conventional.
+    pub fn prefix_search<'a>(&'a self, prefix: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let folding = self.folding;
+        let prefix: String = prefix
+            .chars()
+            .flat_map(|ch| folding.fold_char(if ch == '\\' { '/' } else { ch }))
+            .collect();
+        let start = self
+            .paths
+            .partition_point(|path| path.as_str() < prefix.as_str());
+        self.paths[start..]
+            .iter()
+            .map(String::as_str)
+            .take_while(move |path| path.starts_with(prefix.as_str()))
+    }
+
+    /// Files under `dir` at any depth, `""` is the root.
+    pub fn walk<'a>(&'a self, dir: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let dir = self.folding.make_path_conventional(dir);
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir)
+        };
+        let start = self
+            .paths
+            .partition_point(|path| path.as_str() < prefix.as_str());
+        self.paths[start..]
+            .iter()
+            .map(String::as_str)
+            .take_while(move |path| path.starts_with(prefix.as_str()))
+    }
+
+    /// Immediate children of `dir`, sorted, each subdirectory reported once.
+    pub fn read_dir<'a>(&'a self, dir: &str) -> ReadDir<'a> {
+        let dir = self.folding.make_path_conventional(dir);
+        let prefix_len = if dir.is_empty() { 0 } else { dir.len() + 1 };
+        ReadDir {
+            files: Box::new(self.walk(&dir)),
+            prefix_len,
+            last_dir: None,
+        }
+    }
+
+    /// Number of files directly in each directory, `""` is the root.
+    pub fn file_counts(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for path in &self.paths {
+            let dir = path.rfind('/').map_or("", |pos| &path[..pos]);
+            *counts.entry(dir).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl PathIndex for DirIndex {
+    fn contains_path(&self, path: &str) -> bool {
+        self.contains_conventional(path)
+    }
+
+    fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.paths.iter().map(String::as_str))
+    }
+}
+
+pub struct ReadDir<'a> {
+    files: Box<dyn Iterator<Item = &'a str> + 'a>,
+    prefix_len: usize,
+    last_dir: Option<&'a str>,
+}
+
+impl<'a> Iterator for ReadDir<'a> {
+    type Item = DirEntry<'a>;
+
+    fn next(&mut self) -> Option<DirEntry<'a>> {
+        loop {
+            let path = self.files.next()?;
+            let rest = &path[self.prefix_len..];
+            match rest.find('/') {
+                None => {
+                    return Some(DirEntry {
+                        name: rest,
+                        path,
+                        kind: EntryKind::File,
+                    })
+                }
+                Some(pos) => {
+                    let dir = &path[..self.prefix_len + pos];
+                    // paths of one directory are contiguous once sorted
+                    if self.last_dir == Some(dir) {
+                        continue;
+                    }
+                    self.last_dir = Some(dir);
+                    return Some(DirEntry {
+                        name: &rest[..pos],
+                        path: dir,
+                        kind: EntryKind::Dir,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interner::PathInterner;
+
+    fn index() -> DirIndex {
+        DirIndex::new([
+            "Art\\Critters\\HMJMPSAA.FRM",
+            "art/critters/hmjmpsab.frm",
+            "art/critters/sub/x.frm",
+            "art/critters.lst",
+            "art/items/gun.frm",
+            "text/engl/game.msg",
+        ])
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let index = index();
+        let entries: Vec<_> = index
+            .read_dir("art/critters/")
+            .map(|entry| (entry.name, entry.kind))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("hmjmpsaa.frm", EntryKind::File),
+                ("hmjmpsab.frm", EntryKind::File),
+                ("sub", EntryKind::Dir),
+            ]
+        );
+        let root: Vec<_> = index.read_dir("").map(|entry| entry.path).collect();
+        assert_eq!(root, ["art", "text"]);
+        let art: Vec<_> = index.read_dir("art").map(|entry| entry.name).collect();
+        assert_eq!(art, ["critters.lst", "critters", "items"]);
+    }
+
+    #[test]
+    fn test_walk_and_counts() {
+        let mut index = index();
+        assert_eq!(index.walk("art/critters").count(), 3);
+        assert_eq!(index.walk("").count(), 6);
+        assert!(index.is_dir("art/critters"));
+        assert!(!index.is_dir("art/critter"));
+        assert_eq!(index.prefix_search("art/critters").count(), 4);
+        let counts = index.file_counts();
+        assert_eq!(counts["art/critters"], 2);
+        assert_eq!(counts["art"], 1);
+        assert!(index.insert("Text\\Russ\\Game.msg"));
+        assert!(!index.insert("text/russ/game.msg"));
+        assert!(index.remove("art/items/gun.frm"));
+        assert!(!index.is_dir("art/items"));
+        assert_eq!(index.len(), 6);
+    }
+
+    #[test]
+    fn test_raw_input() {
+        let mut index = index();
+        assert!(index.insert("Art\\Foo.frm"));
+        assert!(index.contains("Art\\Foo.frm"));
+        assert!(index.contains("art/foo.frm"));
+        assert!(index.is_dir("ART\\Critters\\"));
+        assert_eq!(index.walk("Art\\Critters").count(), 3);
+        assert_eq!(index.read_dir("TEXT").count(), 1);
+        assert_eq!(index.prefix_search("Art\\Critters").count(), 4);
+
+        let ascii = PathInterner::with_folding(CaseFolding::Ascii);
+        let mut ascii = DirIndex::from_index(&ascii);
+        assert_eq!(ascii.folding(), CaseFolding::Ascii);
+        ascii.insert("Text\\RUSS\\Я.MSG");
+        assert!(ascii.contains("text/russ/Я.msg"));
+        assert!(!ascii.contains("text/russ/я.msg"));
+        assert_eq!(ascii.paths().collect::<Vec<_>>(), ["text/russ/Я.msg"]);
+    }
+}
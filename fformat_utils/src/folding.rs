@@ -0,0 +1,116 @@
+use std::{char::ToLowercase, cmp::Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CaseFolding {
+    /// Only `A-Z` are folded.
+    Ascii,
+    /// `char::to_lowercase`, a single char may expand (`İ` becomes `i̇`).
+    #[default]
+    Unicode,
+    /// ASCII plus the Cyrillic letters of code page 1251, like the engine folds names.
+    Cp1251,
+}
+
+impl CaseFolding {
+    pub fn fold_char(self, ch: char) -> FoldChar {
+        match self {
+            CaseFolding::Ascii => FoldChar::One(Some(ch.to_ascii_lowercase())),
+            CaseFolding::Unicode => FoldChar::Unicode(ch.to_lowercase()),
+            CaseFolding::Cp1251 => FoldChar::One(Some(cp1251_lowercase(ch))),
+        }
+    }
+
+    pub fn conventional_chars(self, path: &str) -> impl Iterator<Item = char> + Clone + '_ {
+        let path = path.trim().trim_end_matches(['/', '\\']);
+        path.chars().flat_map(move |ch| {
+            if ch == '\\' {
+                FoldChar::One(Some('/'))
+            } else {
+                self.fold_char(ch)
+            }
+        })
+    }
+
+    pub fn make_path_conventional(self, path: &str) -> String {
+        let mut buf = String::with_capacity(path.len());
+        self.write_conventional_path(path, &mut buf);
+        buf
+    }
+
+    pub fn write_conventional_path(self, path: &str, buf: &mut String) {
+        buf.clear();
+        buf.extend(self.conventional_chars(path));
+    }
+
+    /// Compares two raw paths as if both were made conventional, without allocating.
+    pub fn paths_eq(self, a: &str, b: &str) -> bool {
+        self.conventional_chars(a).eq(self.conventional_chars(b))
+    }
+
+    pub fn paths_cmp(self, a: &str, b: &str) -> Ordering {
+        self.conventional_chars(a).cmp(self.conventional_chars(b))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FoldChar {
+    One(Option<char>),
+    Unicode(ToLowercase),
+}
+
+impl Iterator for FoldChar {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            FoldChar::One(ch) => ch.take(),
+            FoldChar::Unicode(iter) => iter.next(),
+        }
+    }
+}
+
+fn cp1251_lowercase(ch: char) -> char {
+    let lower = match ch as u32 {
+        // Ё Ђ Ѓ Є Ѕ І Ї Ј Љ Њ Ћ Ќ Ў Џ
+        code @ 0x0401..=0x040F if code != 0x040D => code + 0x50,
+        // А-Я
+        code @ 0x0410..=0x042F => code + 0x20,
+        // Ґ
+        0x0490 => 0x0491,
+        _ => return ch.to_ascii_lowercase(),
+    };
+    char::from_u32(lower).unwrap_or(ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes() {
+        let path = "Text\\RUSS\\ДИАЛОГ_Ёж_İ.MSG";
+        assert_eq!(
+            CaseFolding::Ascii.make_path_conventional(path),
+            "text/russ/ДИАЛОГ_Ёж_İ.msg"
+        );
+        assert_eq!(
+            CaseFolding::Cp1251.make_path_conventional(path),
+            "text/russ/диалог_ёж_İ.msg"
+        );
+        assert_eq!(
+            CaseFolding::Unicode.make_path_conventional(path),
+            "text/russ/диалог_ёж_i\u{307}.msg"
+        );
+    }
+
+    #[test]
+    fn test_paths_eq() {
+        assert!(CaseFolding::Ascii.paths_eq("Art\\Items\\GUN.FRM", "art/items/gun.frm/"));
+        assert!(!CaseFolding::Ascii.paths_eq("Я.msg", "я.msg"));
+        assert!(CaseFolding::Cp1251.paths_eq("Я.msg", "я.msg"));
+        assert_eq!(
+            CaseFolding::Unicode.paths_cmp("ART/b", "art/A"),
+            Ordering::Greater
+        );
+    }
+}
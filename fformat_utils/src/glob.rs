@@ -0,0 +1,275 @@
+use std::fmt;
+
+use crate::{index::PathIndex, CaseFolding};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub pos: usize,
+    pub msg: &'static str,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern at {}: {}", self.pos, self.msg)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    // `?`
+    One,
+    // `*`, never crosses `/`
+    Star,
+    // `**/`, zero or more whole directories
+    Dirs,
+    // trailing `**`
+    Rest,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// Glob over conventional paths: `*`, `?`, `**` and `[...]` classes.
+///
+/// The pattern is normalized like the paths it matches, so `Art\*.FRM` and `art/*.frm`
+/// are the same pattern.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    folding: CaseFolding,
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        Self::with_folding(pattern, CaseFolding::default())
+    }
+
+    pub fn with_folding(pattern: &str, folding: CaseFolding) -> Result<Self, PatternError> {
+        let chars: Vec<char> = folding.conventional_chars(pattern).collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let ch = chars[i];
+            i += 1;
+            match ch {
+                '?' => tokens.push(Token::One),
+                '*' if chars.get(i) == Some(&'*') => {
+                    i += 1;
+                    let component_start = i == 2 || chars[i - 3] == '/';
+                    match chars.get(i) {
+                        Some('/') if component_start => {
+                            i += 1;
+                            tokens.push(Token::Dirs);
+                        }
+                        None if component_start => tokens.push(Token::Rest),
+                        _ => tokens.push(Token::Star),
+                    }
+                }
+                '*' => tokens.push(Token::Star),
+                '[' => {
+                    let start = i - 1;
+                    let negated = matches!(chars.get(i), Some('!') | Some('^'));
+                    if negated {
+                        i += 1;
+                    }
+                    let mut ranges = Vec::new();
+                    let mut first = true;
+                    loop {
+                        let from = match chars.get(i) {
+                            Some(']') if !first => break,
+                            Some(&from) => from,
+                            None => {
+                                return Err(PatternError {
+                                    pos: start,
+                                    msg: "unclosed character class",
+                                })
+                            }
+                        };
+                        first = false;
+                        i += 1;
+                        match (chars.get(i), chars.get(i + 1)) {
+                            (Some('-'), Some(&to)) if to != ']' => {
+                                if to < from {
+                                    return Err(PatternError {
+                                        pos: i,
+                                        msg: "invalid range in character class",
+                                    });
+                                }
+                                ranges.push((from, to));
+                                i += 2;
+                            }
+                            _ => ranges.push((from, from)),
+                        }
+                    }
+                    i += 1;
+                    tokens.push(Token::Class { negated, ranges });
+                }
+                ch => tokens.push(Token::Literal(ch)),
+            }
+        }
+        Ok(Pattern { folding, tokens })
+    }
+
+    pub fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
+    /// Literal text every match starts with, useful for prefix queries.
+    pub fn literal_prefix(&self) -> String {
+        self.tokens
+            .iter()
+            .map_while(|token| match token {
+                Token::Literal(ch) => Some(*ch),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.tokens
+            .iter()
+            .all(|token| matches!(token, Token::Literal(_)))
+    }
+
+    /// `path` is normalized with the pattern's folding before matching.
+    pub fn matches(&self, path: &str) -> bool {
+        let chars: Vec<char> = self.folding.conventional_chars(path).collect();
+        self.matches_chars(&chars)
+    }
+
+    pub fn select<'a, I>(&'a self, paths: I) -> impl Iterator<Item = &'a str> + 'a
+    where
+        I: IntoIterator<Item = &'a str>,
+        I::IntoIter: 'a,
+    {
+        paths.into_iter().filter(move |path| self.matches(path))
+    }
+
+    pub fn select_from<'a, I: PathIndex + ?Sized>(
+        &'a self,
+        index: &'a I,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.select(index.paths())
+    }
+
+    fn matches_chars(&self, s: &[char]) -> bool {
+        let m = s.len();
+        // `next[j]`: tokens after the current one match `s[j..]`
+        let mut next = vec![false; m + 1];
+        next[m] = true;
+        let mut cur = vec![false; m + 1];
+        for token in self.tokens.iter().rev() {
+            match token {
+                Token::Literal(ch) => {
+                    cur[m] = false;
+                    for j in 0..m {
+                        cur[j] = s[j] == *ch && next[j + 1];
+                    }
+                }
+                Token::One => {
+                    cur[m] = false;
+                    for j in 0..m {
+                        cur[j] = s[j] != '/' && next[j + 1];
+                    }
+                }
+                Token::Class { negated, ranges } => {
+                    cur[m] = false;
+                    for j in 0..m {
+                        let ch = s[j];
+                        let hit = ranges.iter().any(|&(from, to)| from <= ch && ch <= to);
+                        cur[j] = ch != '/' && hit != *negated && next[j + 1];
+                    }
+                }
+                Token::Star => {
+                    cur[m] = next[m];
+                    for j in (0..m).rev() {
+                        cur[j] = next[j] || (s[j] != '/' && cur[j + 1]);
+                    }
+                }
+                Token::Rest => {
+                    cur[m] = next[m];
+                    for j in (0..m).rev() {
+                        cur[j] = next[j] || cur[j + 1];
+                    }
+                }
+                Token::Dirs => {
+                    cur[m] = next[m];
+                    let mut slash = false;
+                    for j in (0..m).rev() {
+                        slash = slash || (s[j] == '/' && next[j + 1]);
+                        cur[j] = next[j] || slash;
+                    }
+                }
+            }
+            std::mem::swap(&mut cur, &mut next);
+        }
+        next[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(pattern: &str, path: &str) -> bool {
+        Pattern::new(pattern).unwrap().matches(path)
+    }
+
+    #[test]
+    fn test_star_and_one() {
+        assert!(check(
+            "art/critters/hmjmps*.fr?",
+            "art/critters/hmjmpsaa.frm"
+        ));
+        assert!(check(
+            "Art\\Critters\\HMJMPS*.FR?",
+            "art/critters/hmjmps.fr0"
+        ));
+        assert!(!check(
+            "art/critters/hmjmps*.fr?",
+            "art/critters/hmjmps/aa.frm"
+        ));
+        assert!(!check("art/*.frm", "art/items/gun.frm"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(check("text/**/*.msg", "text/engl/game.msg"));
+        assert!(check("text/**/*.msg", "text/game.msg"));
+        assert!(check("**/*.msg", "text/engl/sub/game.msg"));
+        assert!(check("text/**", "text/engl/game.msg"));
+        assert!(!check("text/**/*.msg", "texts/game.msg"));
+    }
+
+    #[test]
+    fn test_class() {
+        assert!(check("art/[a-c]*.frm", "art/b1.frm"));
+        assert!(check("art/[!a-c]*.frm", "art/d1.frm"));
+        assert!(!check("art/[!a-c]*.frm", "art/a1.frm"));
+        assert!(check("art/[]x].frm", "art/].frm"));
+        assert_eq!(
+            Pattern::new("art/[abc").unwrap_err(),
+            PatternError {
+                pos: 4,
+                msg: "unclosed character class"
+            }
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let index = vec![
+            "text/engl/game.msg".to_owned(),
+            "text/engl/dlg.msg".to_owned(),
+            "art/items/gun.frm".to_owned(),
+        ];
+        let pattern = Pattern::new("text/**/*.msg").unwrap();
+        assert_eq!(pattern.literal_prefix(), "text/");
+        let found: Vec<_> = pattern.select_from(&index).collect();
+        assert_eq!(found, ["text/engl/game.msg", "text/engl/dlg.msg"]);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{cp1251, CaseFolding};
+
+/// The engine's `Str::GetHash`: Jenkins one-at-a-time over the name bytes.
+///
+/// Bytes are added as signed `char`, so anything above `0x7F` is sign-extended just like
+/// in the engine built with MSVC.
+pub fn name_hash_bytes(bytes: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    for &byte in bytes {
+        hash = hash.wrapping_add(byte as i8 as i32 as u32);
+        hash = hash.wrapping_add(hash << 10);
+        hash ^= hash >> 6;
+    }
+    hash = hash.wrapping_add(hash << 3);
+    hash ^= hash >> 11;
+    hash.wrapping_add(hash << 15)
+}
+
+/// Hash of `name` as the engine computes it: made conventional with code page 1251
+/// folding and encoded in code page 1251.
+pub fn name_hash(name: &str) -> u32 {
+    let conventional = CaseFolding::Cp1251.make_path_conventional(name);
+    name_hash_bytes(&cp1251::encode_lossy(&conventional))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        // published one-at-a-time vectors
+        assert_eq!(name_hash_bytes(b""), 0);
+        assert_eq!(name_hash_bytes(b"a"), 0xca2e_9442);
+        assert_eq!(
+            name_hash_bytes(b"The quick brown fox jumps over the lazy dog"),
+            0x519e_91f5
+        );
+        // the engine's loop in C, built by gcc with `-fsigned-char` like MSVC builds it
+        assert_eq!(name_hash_bytes(b"art/items/gun.frm"), 0x17f6_d9d1);
+        assert_eq!(name_hash_bytes(b"text/engl/game.msg"), 0xf709_a13e);
+        assert_eq!(
+            name_hash_bytes(b"text/russ/\xE4\xE8\xE0\xEB\xEE\xE3.msg"),
+            0x9cda_eab7
+        );
+        assert_eq!(name_hash_bytes(b"\xFF"), 0xae65_a494);
+        assert_eq!(name_hash_bytes(b"\x80\x7F"), 0x6597_7f66);
+    }
+
+    #[test]
+    fn test_name_hash() {
+        assert_eq!(name_hash("Art\\Items\\GUN.FRM"), 0x17f6_d9d1);
+        assert_eq!(name_hash("TEXT\\RUSS\\ДИАЛОГ.msg"), 0x9cda_eab7);
+    }
+}
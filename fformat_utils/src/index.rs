@@ -0,0 +1,120 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::{Read, Seek},
+};
+
+use crate::{archive::ZipPack, interner::PathInterner, CaseFolding};
+
+/// A collection of conventional paths.
+pub trait PathIndex {
+    /// `path` must already be conventional, it is matched exactly: raw paths and
+    /// paths folded differently from the index are not found.
+    fn contains_path(&self, path: &str) -> bool;
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_>;
+
+    /// The folding the paths are conventional with.
+    fn folding(&self) -> CaseFolding {
+        CaseFolding::default()
+    }
+}
+
+impl<R: Read + Seek> PathIndex for ZipPack<R> {
+    fn contains_path(&self, path: &str) -> bool {
+        self.contains_conventional(path)
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(ZipPack::paths(self))
+    }
+
+    fn folding(&self) -> CaseFolding {
+        ZipPack::folding(self)
+    }
+}
+
+impl PathIndex for PathInterner {
+    fn contains_path(&self, path: &str) -> bool {
+        self.get(path).is_some_and(|id| self.resolve(id) == path)
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.iter().map(|(_, path)| path))
+    }
+
+    fn folding(&self) -> CaseFolding {
+        PathInterner::folding(self)
+    }
+}
+
+impl<S: std::hash::BuildHasher> PathIndex for HashSet<String, S> {
+    fn contains_path(&self, path: &str) -> bool {
+        self.contains(path)
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.iter().map(String::as_str))
+    }
+}
+
+impl PathIndex for BTreeSet<String> {
+    fn contains_path(&self, path: &str) -> bool {
+        self.contains(path)
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.iter().map(String::as_str))
+    }
+}
+
+impl PathIndex for [String] {
+    fn contains_path(&self, path: &str) -> bool {
+        self.iter().any(|item| item == path)
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.iter().map(String::as_str))
+    }
+}
+
+impl PathIndex for Vec<String> {
+    fn contains_path(&self, path: &str) -> bool {
+        self.as_slice().contains_path(path)
+    }
+
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        self.as_slice().paths()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::tests::make_zip;
+
+    #[test]
+    fn test_contains_path_contract() {
+        let paths = [
+            "art/items/gun.frm".to_owned(),
+            "text/engl/game.msg".to_owned(),
+        ];
+        let pack = ZipPack::new(make_zip(&[
+            ("Art\\Items\\GUN.FRM", b""),
+            ("text/engl/game.msg", b""),
+        ]))
+        .unwrap();
+        let mut interner = PathInterner::new();
+        for path in &paths {
+            interner.intern(path);
+        }
+        let hash_set: HashSet<String> = paths.iter().cloned().collect();
+        let btree_set: BTreeSet<String> = paths.iter().cloned().collect();
+        let vec = paths.to_vec();
+        let indexes: [&dyn PathIndex; 5] = [&pack, &interner, &hash_set, &btree_set, &vec];
+        for index in indexes {
+            assert!(index.contains_path("art/items/gun.frm"));
+            assert!(!index.contains_path("Art\\Items\\GUN.FRM"));
+            assert!(!index.contains_path("art/items/gun.frm/"));
+            assert!(!index.contains_path("art/items/knife.frm"));
+        }
+    }
+}
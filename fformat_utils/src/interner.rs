@@ -0,0 +1,199 @@
+use crate::CaseFolding;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PathId(u32);
+
+impl PathId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Stores conventional paths back to back in a single arena.
+#[derive(Debug, Clone, Default)]
+pub struct PathInterner {
+    folding: CaseFolding,
+    arena: String,
+    ends: Vec<u32>,
+    hashes: Vec<u32>,
+    // open addressing, `0` is an empty slot, otherwise `id + 1`
+    table: Vec<u32>,
+}
+
+impl PathInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_folding(folding: CaseFolding) -> Self {
+        PathInterner {
+            folding,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_capacity(paths: usize, bytes: usize) -> Self {
+        Self::with_capacity_and_folding(paths, bytes, CaseFolding::default())
+    }
+
+    pub fn with_capacity_and_folding(paths: usize, bytes: usize, folding: CaseFolding) -> Self {
+        let mut interner = PathInterner {
+            folding,
+            arena: String::with_capacity(bytes),
+            ends: Vec::with_capacity(paths),
+            hashes: Vec::with_capacity(paths),
+            table: Vec::new(),
+        };
+        interner.rehash((paths * 2).next_power_of_two().max(16));
+        interner
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    pub fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
+    pub fn arena_len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Normalizes `raw` while hashing, allocating only when the path is new.
+    pub fn intern(&mut self, raw: &str) -> PathId {
+        if self.table.len() < (self.len() + 1) * 2 {
+            self.rehash((self.table.len() * 2).max(16));
+        }
+        let hash = self.hash(raw);
+        let slot = match self.find_slot(raw, hash) {
+            Ok(id) => return id,
+            Err(slot) => slot,
+        };
+        self.arena.extend(self.folding.conventional_chars(raw));
+        // the table stores `id + 1`
+        let stored = u32::try_from(self.ends.len() + 1).expect("fewer than u32::MAX paths");
+        let end = u32::try_from(self.arena.len()).expect("at most 4 GiB of paths");
+        let id = PathId(stored - 1);
+        self.ends.push(end);
+        self.hashes.push(hash);
+        self.table[slot] = stored;
+        id
+    }
+
+    /// Looks up `raw` in any case and with either separator, without allocating.
+    pub fn get(&self, raw: &str) -> Option<PathId> {
+        if self.table.is_empty() {
+            return None;
+        }
+        self.find_slot(raw, self.hash(raw)).ok()
+    }
+
+    pub fn contains(&self, raw: &str) -> bool {
+        self.get(raw).is_some()
+    }
+
+    pub fn resolve(&self, id: PathId) -> &str {
+        let end = self.ends[id.index()] as usize;
+        let start = match id.index() {
+            0 => 0,
+            index => self.ends[index - 1] as usize,
+        };
+        &self.arena[start..end]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PathId, &str)> {
+        (0..self.len() as u32).map(move |id| (PathId(id), self.resolve(PathId(id))))
+    }
+
+    fn find_slot(&self, raw: &str, hash: u32) -> Result<PathId, usize> {
+        let mask = self.table.len() - 1;
+        let mut slot = hash as usize & mask;
+        loop {
+            match self.table[slot] {
+                0 => return Err(slot),
+                stored => {
+                    let id = PathId(stored - 1);
+                    if self.hashes[id.index()] == hash
+                        && self
+                            .resolve(id)
+                            .chars()
+                            .eq(self.folding.conventional_chars(raw))
+                    {
+                        return Ok(id);
+                    }
+                }
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    // FNV-1a over the normalized chars
+    fn hash(&self, raw: &str) -> u32 {
+        self.folding
+            .conventional_chars(raw)
+            .fold(0x811c_9dc5, |hash, ch| {
+                (hash ^ ch as u32).wrapping_mul(0x0100_0193)
+            })
+    }
+
+    fn rehash(&mut self, size: usize) {
+        let mask = size - 1;
+        self.table = vec![0; size];
+        for (id, &hash) in self.hashes.iter().enumerate() {
+            let mut slot = hash as usize & mask;
+            while self.table[slot] != 0 {
+                slot = (slot + 1) & mask;
+            }
+            self.table[slot] = u32::try_from(id + 1).expect("ids fit when interned");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_dedup() {
+        let mut interner = PathInterner::new();
+        let a = interner.intern("Art\\Items\\GUN.FRM");
+        let b = interner.intern("art/items/gun.frm/");
+        let c = interner.intern("art/items/knife.frm");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(a), "art/items/gun.frm");
+        assert_eq!(interner.get(" ART/ITEMS/KNIFE.FRM "), Some(c));
+        assert_eq!(interner.get("art/items/pistol.frm"), None);
+    }
+
+    #[test]
+    fn test_intern_folding() {
+        let mut interner = PathInterner::with_folding(CaseFolding::Ascii);
+        let id = interner.intern("Text\\RUSS\\Я.MSG");
+        assert_eq!(interner.resolve(id), "text/russ/Я.msg");
+        assert_eq!(interner.get("text/russ/я.msg"), None);
+
+        let mut interner = PathInterner::with_capacity_and_folding(4, 64, CaseFolding::Cp1251);
+        let id = interner.intern("Text\\RUSS\\Я.MSG");
+        assert_eq!(interner.get("text/russ/я.msg"), Some(id));
+        assert_eq!(interner.folding(), CaseFolding::Cp1251);
+    }
+
+    #[test]
+    fn test_intern_grow() {
+        let mut interner = PathInterner::new();
+        let ids: Vec<_> = (0..1000)
+            .map(|i| interner.intern(&format!("Data\\File{}.TXT", i)))
+            .collect();
+        for (i, id) in ids.into_iter().enumerate() {
+            assert_eq!(interner.resolve(id), format!("data/file{}.txt", i));
+            assert_eq!(interner.get(&format!("data/file{}.txt", i)), Some(id));
+        }
+    }
+}
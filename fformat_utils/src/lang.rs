@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use crate::{make_path_conventional, PathIndex};
+
+const PLACEHOLDER: &str = "{lang}";
+
+/// Conventional path with a `{lang}` placeholder, like `text/{lang}/game.msg`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Template(String);
+
+impl Template {
+    pub fn new(raw: &str) -> Self {
+        Template(make_path_conventional(raw))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn has_placeholder(&self) -> bool {
+        self.0.contains(PLACEHOLDER)
+    }
+
+    pub fn expand(&self, lang: &str) -> String {
+        self.0.replace(PLACEHOLDER, &make_path_conventional(lang))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved<'a> {
+    pub path: String,
+    pub lang: &'a str,
+    /// Served by a language other than the first one.
+    pub fallback: bool,
+}
+
+/// Resolves templates against `index` trying languages in order.
+pub struct LangResolver<'a, I: ?Sized> {
+    index: &'a I,
+    languages: Vec<String>,
+}
+
+impl<'a, I: PathIndex + ?Sized> LangResolver<'a, I> {
+    /// The first of `languages` is the preferred one, the rest are fallbacks.
+    pub fn new<L, S>(index: &'a I, languages: L) -> Self
+    where
+        L: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        LangResolver {
+            index,
+            languages: languages
+                .into_iter()
+                .map(|lang| make_path_conventional(lang.as_ref()))
+                .collect(),
+        }
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    pub fn resolve(&self, template: &Template) -> Option<Resolved<'_>> {
+        self.languages.iter().enumerate().find_map(|(index, lang)| {
+            let path = template.expand(lang);
+            self.index.contains_path(&path).then(|| Resolved {
+                path,
+                lang,
+                fallback: index > 0,
+            })
+        })
+    }
+
+    /// Expanded paths of `templates` absent from the index, per language.
+    pub fn missing<'t, T>(&self, templates: T) -> BTreeMap<&str, Vec<String>>
+    where
+        T: IntoIterator<Item = &'t Template>,
+    {
+        let mut missing: BTreeMap<&str, Vec<String>> = self
+            .languages
+            .iter()
+            .map(|lang| (lang.as_str(), Vec::new()))
+            .collect();
+        for template in templates {
+            for lang in &self.languages {
+                let path = template.expand(lang);
+                if !self.index.contains_path(&path) {
+                    missing.get_mut(lang.as_str()).unwrap().push(path);
+                }
+            }
+        }
+        missing
+    }
+
+    /// Templates for every path under `dir/{lang}/` of any configured language.
+    pub fn templates_under(&self, dir: &str) -> Vec<Template> {
+        let dir = make_path_conventional(dir);
+        let mut templates: Vec<Template> = self
+            .index
+            .paths()
+            .filter_map(|path| {
+                let rest = path.strip_prefix(dir.as_str())?.strip_prefix('/')?;
+                let (lang, rest) = rest.split_once('/')?;
+                self.languages
+                    .iter()
+                    .any(|known| known == lang)
+                    .then(|| Template(format!("{}/{}/{}", dir, PLACEHOLDER, rest)))
+            })
+            .collect();
+        templates.sort();
+        templates.dedup();
+        templates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::DirIndex;
+
+    fn index() -> DirIndex {
+        DirIndex::new([
+            "text/engl/game.msg",
+            "text/engl/dlg.msg",
+            "text/russ/game.msg",
+            "sound/speech/engl/hello.acm",
+        ])
+    }
+
+    #[test]
+    fn test_resolve() {
+        let index = index();
+        let resolver = LangResolver::new(&index, ["RUSS", "engl"]);
+        let game = resolver
+            .resolve(&Template::new("Text\\{LANG}\\Game.MSG"))
+            .unwrap();
+        assert_eq!(game.path, "text/russ/game.msg");
+        assert!(!game.fallback);
+        let dlg = resolver
+            .resolve(&Template::new("text/{lang}/dlg.msg"))
+            .unwrap();
+        assert_eq!(
+            dlg,
+            Resolved {
+                path: "text/engl/dlg.msg".into(),
+                lang: "engl",
+                fallback: true
+            }
+        );
+        assert_eq!(resolver.resolve(&Template::new("text/{lang}/x.msg")), None);
+    }
+
+    #[test]
+    fn test_missing() {
+        let index = index();
+        let resolver = LangResolver::new(&index, ["engl", "russ", "germ"]);
+        let templates = resolver.templates_under("text");
+        assert_eq!(
+            templates,
+            [
+                Template::new("text/{lang}/dlg.msg"),
+                Template::new("text/{lang}/game.msg")
+            ]
+        );
+        let missing = resolver.missing(&templates);
+        assert!(missing["engl"].is_empty());
+        assert_eq!(missing["russ"], ["text/russ/dlg.msg"]);
+        assert_eq!(missing["germ"], ["text/germ/dlg.msg", "text/germ/game.msg"]);
+    }
+}
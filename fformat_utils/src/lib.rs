@@ -1,3 +1,24 @@
+pub mod archive;
+pub mod batch;
+pub mod cp1251;
+pub mod dir;
+pub mod folding;
+pub mod glob;
+pub mod hash;
+pub mod index;
+pub mod interner;
+pub mod lang;
+pub mod native;
+pub mod path;
+pub mod safety;
+pub mod suggest;
+#[cfg(all(feature = "watch", target_os = "linux"))]
+pub mod watch;
+
+pub use folding::CaseFolding;
+pub use index::PathIndex;
+pub use path::{ConventionalPath, ConventionalPathBuf};
+
 pub fn make_path_conventional(path: &str) -> String {
     let mut buf = String::with_capacity(path.len());
     write_conventional_path(path, &mut buf);
@@ -5,13 +26,9 @@ pub fn make_path_conventional(path: &str) -> String {
 }
 
 pub fn write_conventional_path(path: &str, buf: &mut String) {
-    buf.clear();
-    let path = path.trim().trim_end_matches(|ch| ch == '/' || ch == '\\');
-    for ch in path.chars() {
-        if ch == '\\' {
-            buf.push('/');
-        } else {
-            buf.extend(ch.to_lowercase())
-        }
-    }
+    CaseFolding::Unicode.write_conventional_path(path, buf)
+}
+
+pub fn conventional_chars(path: &str) -> impl Iterator<Item = char> + Clone + '_ {
+    CaseFolding::Unicode.conventional_chars(path)
 }
@@ -0,0 +1,322 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{CaseFolding, ConventionalPathBuf};
+
+#[derive(Debug)]
+pub enum NativePathError {
+    NotUnderRoot(PathBuf),
+    ParentDir(PathBuf),
+    NotUnicode(PathBuf),
+    NotFound(String),
+    Ambiguous {
+        path: String,
+        candidates: Vec<PathBuf>,
+    },
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for NativePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NativePathError::NotUnderRoot(path) => {
+                write!(f, "{} is not under the data root", path.display())
+            }
+            NativePathError::ParentDir(path) => {
+                write!(f, "{} contains `..`", path.display())
+            }
+            NativePathError::NotUnicode(path) => {
+                write!(f, "{} is not valid unicode", path.display())
+            }
+            NativePathError::NotFound(path) => write!(f, "{} not found", path),
+            NativePathError::Ambiguous { path, candidates } => {
+                write!(f, "{} is ambiguous:", path)?;
+                for candidate in candidates {
+                    write!(f, " {}", candidate.display())?;
+                }
+                Ok(())
+            }
+            NativePathError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for NativePathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NativePathError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// `path` is either relative to `root` or starts with it.
+pub fn conventional_from_native(
+    root: &Path,
+    path: &Path,
+) -> Result<ConventionalPathBuf, NativePathError> {
+    native_relative(root, path, false).map(|raw| ConventionalPathBuf::new(&raw))
+}
+
+/// Like [`conventional_from_native`], but replaces non-UTF-8 names with `\u{FFFD}`.
+pub fn conventional_from_native_lossy(
+    root: &Path,
+    path: &Path,
+) -> Result<ConventionalPathBuf, NativePathError> {
+    native_relative(root, path, true).map(|raw| ConventionalPathBuf::new(&raw))
+}
+
+// joins the components relative to `root` with `/`, as is
+fn native_relative(root: &Path, path: &Path, lossy: bool) -> Result<String, NativePathError> {
+    let relative = if path.is_absolute() || path.starts_with(root) {
+        path.strip_prefix(root)
+            .map_err(|_| NativePathError::NotUnderRoot(path.to_owned()))?
+    } else {
+        path
+    };
+    let mut buf = String::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(name) => {
+                if !buf.is_empty() {
+                    buf.push('/');
+                }
+                match name.to_str() {
+                    Some(name) => buf.push_str(name),
+                    None if lossy => buf.push_str(&name.to_string_lossy()),
+                    None => return Err(NativePathError::NotUnicode(path.to_owned())),
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => return Err(NativePathError::ParentDir(path.to_owned())),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(NativePathError::NotUnderRoot(path.to_owned()))
+            }
+        }
+    }
+    Ok(buf)
+}
+
+/// Maps conventional paths back to real names on a case-sensitive file system.
+///
+/// Directory listings are cached, call [`NativeResolver::invalidate`] after changes on disk.
+#[derive(Debug)]
+pub struct NativeResolver {
+    root: PathBuf,
+    folding: CaseFolding,
+    // native dir -> folded entry name -> real entry names
+    dirs: HashMap<PathBuf, HashMap<String, Vec<OsString>>>,
+}
+
+impl NativeResolver {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self::with_folding(root, CaseFolding::default())
+    }
+
+    pub fn with_folding<P: Into<PathBuf>>(root: P, folding: CaseFolding) -> Self {
+        NativeResolver {
+            root: root.into(),
+            folding,
+            dirs: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Folds with the resolver's [`CaseFolding`].
+    pub fn to_conventional(&self, path: &Path) -> Result<String, NativePathError> {
+        let raw = native_relative(&self.root, path, false)?;
+        Ok(self.folding.make_path_conventional(&raw))
+    }
+
+    /// `path` is folded with the resolver's [`CaseFolding`] first.
+    pub fn resolve(&mut self, path: &str) -> Result<PathBuf, NativePathError> {
+        let path = self.folding.make_path_conventional(path);
+        let mut native = self.root.clone();
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            let listing = match self.dirs.get(&native) {
+                Some(listing) => listing,
+                None => {
+                    let listing = read_listing(&native, self.folding)?;
+                    self.dirs.entry(native.clone()).or_insert(listing)
+                }
+            };
+            match listing.get(component).map(Vec::as_slice) {
+                Some([name]) => native.push(name),
+                Some(names) if !names.is_empty() => {
+                    return Err(NativePathError::Ambiguous {
+                        path,
+                        candidates: names.iter().map(|name| native.join(name)).collect(),
+                    })
+                }
+                _ => return Err(NativePathError::NotFound(path)),
+            }
+        }
+        Ok(native)
+    }
+
+    /// Drops cached listings of `dir` and everything below it.
+    pub fn invalidate(&mut self, dir: &Path) {
+        self.dirs.retain(|cached, _| !cached.starts_with(dir));
+    }
+
+    pub fn clear(&mut self) {
+        self.dirs.clear();
+    }
+}
+
+fn read_listing(
+    dir: &Path,
+    folding: CaseFolding,
+) -> Result<HashMap<String, Vec<OsString>>, NativePathError> {
+    let io_err = |err| NativePathError::Io(dir.to_owned(), err);
+    let mut listing: HashMap<String, Vec<OsString>> = HashMap::new();
+    for entry in fs::read_dir(dir).map_err(io_err)? {
+        let name = entry.map_err(io_err)?.file_name();
+        // no conventional path names it, and lossy names would clash with each other
+        let folded = match name.to_str() {
+            Some(name) => folding.make_path_conventional(name),
+            None => continue,
+        };
+        listing.entry(folded).or_default().push(name);
+    }
+    for names in listing.values_mut() {
+        names.sort();
+    }
+    Ok(listing)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Removed on drop, also when an assertion fails.
+    pub(crate) struct TempRoot(PathBuf);
+
+    impl TempRoot {
+        pub(crate) fn new(name: &str) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("fformat_utils_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            TempRoot(root)
+        }
+    }
+
+    impl std::ops::Deref for TempRoot {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_to_conventional() {
+        let root = Path::new("/data");
+        assert_eq!(
+            conventional_from_native(root, Path::new("/data/Art/Items/GUN.FRM"))
+                .unwrap()
+                .as_str(),
+            "art/items/gun.frm"
+        );
+        assert_eq!(
+            conventional_from_native(root, Path::new("./Text/ENGL/game.msg"))
+                .unwrap()
+                .as_str(),
+            "text/engl/game.msg"
+        );
+        assert!(matches!(
+            conventional_from_native(root, Path::new("/other/gun.frm")),
+            Err(NativePathError::NotUnderRoot(_))
+        ));
+        assert!(matches!(
+            conventional_from_native(root, Path::new("art/../gun.frm")),
+            Err(NativePathError::ParentDir(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"art/\xC3\xE0\xEC.frm"));
+        assert!(matches!(
+            conventional_from_native(Path::new(""), path),
+            Err(NativePathError::NotUnicode(_))
+        ));
+        assert_eq!(
+            conventional_from_native_lossy(Path::new(""), path)
+                .unwrap()
+                .as_str(),
+            "art/\u{FFFD}\u{FFFD}\u{FFFD}.frm"
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let root = TempRoot::new("resolve");
+        fs::create_dir_all(root.join("Art/Items")).unwrap();
+        fs::write(root.join("Art/Items/GUN.FRM"), b"").unwrap();
+        let mut resolver = NativeResolver::new(&*root);
+        assert_eq!(
+            resolver
+                .to_conventional(&root.join("Art/Items/GUN.FRM"))
+                .unwrap(),
+            "art/items/gun.frm"
+        );
+        let gun = "Art\\Items\\gun.frm";
+        assert_eq!(
+            resolver.resolve(gun).unwrap(),
+            root.join("Art/Items/GUN.FRM")
+        );
+
+        fs::write(root.join("Art/Items/gun.frm"), b"").unwrap();
+        assert!(resolver.resolve(gun).is_ok());
+        resolver.invalidate(&root.join("Art"));
+        match resolver.resolve(gun) {
+            Err(NativePathError::Ambiguous { candidates, .. }) => assert_eq!(
+                candidates,
+                [
+                    root.join("Art/Items/GUN.FRM"),
+                    root.join("Art/Items/gun.frm")
+                ]
+            ),
+            res => panic!("expected ambiguity, got {:?}", res),
+        }
+        assert!(matches!(
+            resolver.resolve("art/knife.frm"),
+            Err(NativePathError::NotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = TempRoot::new("resolve_non_utf8");
+        for name in [&b"\xC3\xE0\xEC.frm"[..], b"\xC4\xE0\xEC.frm", b"gun.frm"] {
+            fs::write(root.join(std::ffi::OsStr::from_bytes(name)), b"").unwrap();
+        }
+        let mut resolver = NativeResolver::new(&*root);
+        assert!(matches!(
+            resolver.resolve("\u{FFFD}\u{FFFD}\u{FFFD}.frm"),
+            Err(NativePathError::NotFound(_))
+        ));
+        assert_eq!(resolver.resolve("GUN.FRM").unwrap(), root.join("gun.frm"));
+    }
+}
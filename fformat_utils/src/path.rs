@@ -0,0 +1,354 @@
+use std::{borrow::Borrow, fmt, ops::Deref};
+
+use crate::CaseFolding;
+
+/// `path` is unchanged when made conventional with `folding`.
+pub fn is_conventional(path: &str, folding: CaseFolding) -> bool {
+    folding.conventional_chars(path).eq(path.chars())
+}
+
+/// Borrowed conventional path, like `Path` is to `PathBuf`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct ConventionalPath(str);
+
+impl ConventionalPath {
+    /// `path` must be conventional with `folding`, the one of the pack it comes from.
+    pub fn new(path: &str, folding: CaseFolding) -> Option<&Self> {
+        if is_conventional(path, folding) {
+            Some(Self::new_unchecked(path))
+        } else {
+            None
+        }
+    }
+
+    fn new_unchecked(path: &str) -> &Self {
+        // SAFETY: `ConventionalPath` is a `repr(transparent)` wrapper around `str`
+        unsafe { &*(path as *const str as *const ConventionalPath) }
+    }
+
+    pub fn empty() -> &'static Self {
+        Self::new_unchecked("")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = &str> + Clone {
+        self.0.split('/').filter(|component| !component.is_empty())
+    }
+
+    /// `None` only for the empty path, a single component has the empty parent.
+    pub fn parent(&self) -> Option<&Self> {
+        if self.is_empty() {
+            return None;
+        }
+        let path = self.0.trim_end_matches('/');
+        let parent = match path.rfind('/') {
+            Some(pos) => path[..pos].trim_end_matches('/'),
+            None => "",
+        };
+        Some(Self::new_unchecked(parent))
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.components().next_back()
+    }
+
+    pub fn file_stem(&self) -> Option<&str> {
+        self.file_name().map(|name| split_extension(name).0)
+    }
+
+    /// `Some("")` for a trailing dot, `None` for dot files and names without a dot.
+    pub fn extension(&self) -> Option<&str> {
+        self.file_name().and_then(|name| split_extension(name).1)
+    }
+
+    /// `folding` must be the one `self` is conventional with.
+    pub fn with_extension(&self, extension: &str, folding: CaseFolding) -> ConventionalPathBuf {
+        let mut buf = self.to_owned();
+        buf.set_extension(extension, folding);
+        buf
+    }
+
+    /// `other` is made conventional with `folding` before joining, which must be the
+    /// one `self` is conventional with.
+    pub fn join(&self, other: &str, folding: CaseFolding) -> ConventionalPathBuf {
+        let mut buf = self.to_owned();
+        buf.push(other, folding);
+        buf
+    }
+
+    /// Component-wise, `art/item` is not a prefix of `art/items/gun.frm`.
+    pub fn starts_with(&self, prefix: &ConventionalPath) -> bool {
+        self.strip_prefix(prefix).is_some()
+    }
+
+    pub fn strip_prefix(&self, prefix: &ConventionalPath) -> Option<&Self> {
+        let prefix = prefix.0.trim_end_matches('/');
+        if prefix.is_empty() {
+            return Some(self);
+        }
+        let rest = self.0.strip_prefix(prefix)?;
+        if rest.is_empty() {
+            Some(Self::empty())
+        } else if rest.starts_with('/') {
+            Some(Self::new_unchecked(rest.trim_start_matches('/')))
+        } else {
+            None
+        }
+    }
+}
+
+impl ToOwned for ConventionalPath {
+    type Owned = ConventionalPathBuf;
+
+    fn to_owned(&self) -> ConventionalPathBuf {
+        ConventionalPathBuf(self.0.to_owned())
+    }
+}
+
+impl AsRef<str> for ConventionalPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ConventionalPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for ConventionalPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConventionalPathBuf(String);
+
+impl ConventionalPathBuf {
+    /// Makes `raw` conventional with the default folding.
+    pub fn new(raw: &str) -> Self {
+        Self::with_folding(raw, CaseFolding::default())
+    }
+
+    pub fn with_folding(raw: &str, folding: CaseFolding) -> Self {
+        ConventionalPathBuf(folding.make_path_conventional(raw))
+    }
+
+    pub fn as_path(&self) -> &ConventionalPath {
+        ConventionalPath::new_unchecked(&self.0)
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// `folding` must be the one `self` is conventional with.
+    pub fn push(&mut self, raw: &str, folding: CaseFolding) {
+        let tail = folding.make_path_conventional(raw);
+        let tail = tail.trim_start_matches('/');
+        if tail.is_empty() {
+            return;
+        }
+        if !self.0.is_empty() {
+            self.0.push('/');
+        }
+        self.0.push_str(tail);
+    }
+
+    pub fn pop(&mut self) -> bool {
+        match self.as_path().parent() {
+            Some(parent) => {
+                let len = parent.0.len();
+                self.0.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// An empty `extension` removes the current one. `folding` must be the one `self`
+    /// is conventional with.
+    pub fn set_extension(&mut self, extension: &str, folding: CaseFolding) -> bool {
+        let stem_len = match self.as_path().file_name() {
+            Some(name) => {
+                let (stem, _) = split_extension(name);
+                self.0.len() - name.len() + stem.len()
+            }
+            None => return false,
+        };
+        self.0.truncate(stem_len);
+        let extension = folding.make_path_conventional(extension.trim_start_matches('.'));
+        if !extension.is_empty() {
+            self.0.push('.');
+            self.0.extend(extension.chars().filter(|&ch| ch != '/'));
+        }
+        true
+    }
+}
+
+impl Deref for ConventionalPathBuf {
+    type Target = ConventionalPath;
+
+    fn deref(&self) -> &ConventionalPath {
+        self.as_path()
+    }
+}
+
+impl Borrow<ConventionalPath> for ConventionalPathBuf {
+    fn borrow(&self) -> &ConventionalPath {
+        self.as_path()
+    }
+}
+
+impl AsRef<ConventionalPath> for ConventionalPathBuf {
+    fn as_ref(&self) -> &ConventionalPath {
+        self.as_path()
+    }
+}
+
+impl AsRef<str> for ConventionalPathBuf {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ConventionalPathBuf {
+    fn from(raw: &str) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl From<ConventionalPathBuf> for String {
+    fn from(path: ConventionalPathBuf) -> Self {
+        path.0
+    }
+}
+
+impl fmt::Debug for ConventionalPathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_path(), f)
+    }
+}
+
+impl fmt::Display for ConventionalPathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_path(), f)
+    }
+}
+
+fn split_extension(name: &str) -> (&str, Option<&str>) {
+    match name.rfind('.') {
+        Some(0) | None => (name, None),
+        _ if name == ".." => (name, None),
+        Some(pos) => (&name[..pos], Some(&name[pos + 1..])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNICODE: CaseFolding = CaseFolding::Unicode;
+
+    fn path(raw: &str) -> ConventionalPathBuf {
+        ConventionalPathBuf::new(raw)
+    }
+
+    #[test]
+    fn test_components() {
+        let gun = path("Art\\Items\\GUN.FRM");
+        assert_eq!(
+            gun.components().collect::<Vec<_>>(),
+            ["art", "items", "gun.frm"]
+        );
+        assert_eq!(gun.parent().unwrap().as_str(), "art/items");
+        assert_eq!(gun.file_name(), Some("gun.frm"));
+        assert_eq!(gun.file_stem(), Some("gun"));
+        assert_eq!(gun.extension(), Some("frm"));
+        assert_eq!(path("gun.frm").parent(), Some(ConventionalPath::empty()));
+        assert_eq!(path("").parent(), None);
+        assert_eq!(
+            ConventionalPath::new("Art/gun.frm", CaseFolding::Ascii),
+            None
+        );
+        let russian = "text/russ/Я.msg";
+        assert!(ConventionalPath::new(russian, CaseFolding::Ascii).is_some());
+        assert_eq!(ConventionalPath::new(russian, CaseFolding::Cp1251), None);
+    }
+
+    #[test]
+    fn test_extension_edge_cases() {
+        assert_eq!(path("text/readme").extension(), None);
+        assert_eq!(path("text/file.").extension(), Some(""));
+        assert_eq!(path("text/file.").file_stem(), Some("file"));
+        assert_eq!(path("text/.msg").extension(), None);
+        assert_eq!(path("text/.msg").file_stem(), Some(".msg"));
+        assert_eq!(path("a/b.tar.gz").extension(), Some("gz"));
+        assert_eq!(
+            path("art/gun.frm").with_extension(".FR0", UNICODE).as_str(),
+            "art/gun.fr0"
+        );
+        assert_eq!(
+            path("art/gun.frm").with_extension("", UNICODE).as_str(),
+            "art/gun"
+        );
+        assert_eq!(
+            path("art/gun").with_extension("frm", UNICODE).as_str(),
+            "art/gun.frm"
+        );
+    }
+
+    #[test]
+    fn test_join_and_prefix() {
+        let art = path("art");
+        assert_eq!(
+            art.join("\\Items\\Gun.FRM", UNICODE).as_str(),
+            "art/items/gun.frm"
+        );
+        assert_eq!(
+            ConventionalPath::empty().join("Art", UNICODE).as_str(),
+            "art"
+        );
+        let gun = art.join("items/gun.frm", UNICODE);
+        assert!(gun.starts_with(&path("art/items")));
+        assert!(!gun.starts_with(&path("art/item")));
+        assert_eq!(
+            gun.strip_prefix(&path("art")).unwrap().as_str(),
+            "items/gun.frm"
+        );
+        assert_eq!(gun.strip_prefix(&gun), Some(ConventionalPath::empty()));
+        let mut buf = gun.clone();
+        assert!(buf.pop());
+        assert_eq!(buf.as_str(), "art/items");
+    }
+
+    #[test]
+    fn test_other_foldings() {
+        for folding in [CaseFolding::Ascii, CaseFolding::Cp1251] {
+            let dir = ConventionalPathBuf::with_folding("Text\\RUSS\\Я", folding);
+            let msg = dir.join("ДИАЛОГ.MSG", folding);
+            assert!(is_conventional(msg.as_str(), folding), "{}", msg);
+            let txt = msg.with_extension("ТХТ", folding);
+            assert!(is_conventional(txt.as_str(), folding), "{}", txt);
+        }
+        let ascii = ConventionalPath::new("text/russ/Я", CaseFolding::Ascii).unwrap();
+        assert_eq!(
+            ascii.join("ДИАЛОГ.MSG", CaseFolding::Ascii).as_str(),
+            "text/russ/Я/ДИАЛОГ.msg"
+        );
+        let mut cp1251 = ConventionalPathBuf::with_folding("Text/Я", CaseFolding::Cp1251);
+        cp1251.push("ДИАЛОГ.MSG", CaseFolding::Cp1251);
+        assert!(cp1251.set_extension("ТХТ", CaseFolding::Cp1251));
+        assert_eq!(cp1251.as_str(), "text/я/диалог.тхт");
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{ConventionalPath, ConventionalPathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnsafeReason {
+    Empty,
+    Absolute,
+    DriveLetter,
+    EmptyComponent,
+    CurDir,
+    ParentDir,
+    NulByte,
+    InvalidChar(char),
+    ReservedName(String),
+    TrailingDotOrSpace(String),
+}
+
+impl fmt::Display for UnsafeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsafeReason::Empty => f.write_str("path is empty"),
+            UnsafeReason::Absolute => f.write_str("path is absolute"),
+            UnsafeReason::DriveLetter => f.write_str("path starts with a drive letter"),
+            UnsafeReason::EmptyComponent => f.write_str("path has an empty component"),
+            UnsafeReason::CurDir => f.write_str("path has a `.` component"),
+            UnsafeReason::ParentDir => f.write_str("path has a `..` component"),
+            UnsafeReason::NulByte => f.write_str("path contains a NUL byte"),
+            UnsafeReason::InvalidChar(ch) => write!(f, "path contains invalid char {:?}", ch),
+            UnsafeReason::ReservedName(name) => write!(f, "`{}` is a reserved name", name),
+            UnsafeReason::TrailingDotOrSpace(name) => {
+                write!(f, "`{}` ends with a dot or space", name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsafePath {
+    pub path: String,
+    pub reason: UnsafeReason,
+}
+
+impl fmt::Display for UnsafePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsafe path {:?}: {}", self.path, self.reason)
+    }
+}
+
+impl std::error::Error for UnsafePath {}
+
+const RESERVED: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Checks that `path` stays inside the target directory on every platform once extracted.
+pub fn check_extraction_path(path: &ConventionalPath) -> Result<(), UnsafeReason> {
+    let path = path.as_str();
+    if path.is_empty() {
+        return Err(UnsafeReason::Empty);
+    }
+    if path.starts_with('/') {
+        return Err(UnsafeReason::Absolute);
+    }
+    for (index, component) in path.split('/').enumerate() {
+        match component {
+            "" => return Err(UnsafeReason::EmptyComponent),
+            "." => return Err(UnsafeReason::CurDir),
+            ".." => return Err(UnsafeReason::ParentDir),
+            _ => {}
+        }
+        for ch in component.chars() {
+            match ch {
+                '\0' => return Err(UnsafeReason::NulByte),
+                ':' if index == 0 && component.len() == 2 => return Err(UnsafeReason::DriveLetter),
+                ':' | '<' | '>' | '"' | '|' | '?' | '*' => {
+                    return Err(UnsafeReason::InvalidChar(ch))
+                }
+                ch if ch.is_control() => return Err(UnsafeReason::InvalidChar(ch)),
+                _ => {}
+            }
+        }
+        let base = component.split('.').next().unwrap_or(component).trim_end();
+        if RESERVED.contains(&base) {
+            return Err(UnsafeReason::ReservedName(component.to_owned()));
+        }
+        if component.ends_with('.') || component.ends_with(' ') {
+            return Err(UnsafeReason::TrailingDotOrSpace(component.to_owned()));
+        }
+    }
+    Ok(())
+}
+
+impl ConventionalPath {
+    pub fn is_safe_for_extraction(&self) -> bool {
+        check_extraction_path(self).is_ok()
+    }
+
+    /// Joins `self` onto `root`, refusing paths that would escape it.
+    pub fn extraction_target(&self, root: &Path) -> Result<PathBuf, UnsafePath> {
+        check_extraction_path(self).map_err(|reason| UnsafePath {
+            path: self.as_str().to_owned(),
+            reason,
+        })?;
+        Ok(self
+            .components()
+            .fold(root.to_owned(), |buf, component| buf.join(component)))
+    }
+}
+
+impl ConventionalPathBuf {
+    /// Makes `raw` conventional and refuses it if unsafe for extraction.
+    pub fn new_safe(raw: &str) -> Result<Self, UnsafePath> {
+        let path = Self::new(raw);
+        match check_extraction_path(&path) {
+            Ok(()) => Ok(path),
+            Err(reason) => Err(UnsafePath {
+                path: path.into_string(),
+                reason,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(raw: &str) -> Result<(), UnsafeReason> {
+        ConventionalPathBuf::new_safe(raw)
+            .map(|_| ())
+            .map_err(|err| err.reason)
+    }
+
+    #[test]
+    fn test_safe() {
+        assert_eq!(check("Art\\Items\\GUN.FRM"), Ok(()));
+        assert_eq!(check("text/engl/.hidden"), Ok(()));
+        assert_eq!(check("art/console.frm"), Ok(()));
+    }
+
+    #[test]
+    fn test_unsafe() {
+        assert_eq!(check(""), Err(UnsafeReason::Empty));
+        assert_eq!(check("/etc/passwd"), Err(UnsafeReason::Absolute));
+        assert_eq!(check("\\\\server\\share"), Err(UnsafeReason::Absolute));
+        assert_eq!(check("C:\\Windows\\x.dll"), Err(UnsafeReason::DriveLetter));
+        assert_eq!(check("art//gun.frm"), Err(UnsafeReason::EmptyComponent));
+        assert_eq!(check("art/../../gun.frm"), Err(UnsafeReason::ParentDir));
+        assert_eq!(check("./gun.frm"), Err(UnsafeReason::CurDir));
+        assert_eq!(check("art/gun\0.frm"), Err(UnsafeReason::NulByte));
+        assert_eq!(
+            check("art/gun.frm:ads"),
+            Err(UnsafeReason::InvalidChar(':'))
+        );
+        assert_eq!(
+            check("text/CON"),
+            Err(UnsafeReason::ReservedName("con".into()))
+        );
+        assert_eq!(
+            check("text/aux.msg"),
+            Err(UnsafeReason::ReservedName("aux.msg".into()))
+        );
+        assert_eq!(
+            check("text/game. /x"),
+            Err(UnsafeReason::TrailingDotOrSpace("game. ".into()))
+        );
+    }
+
+    #[test]
+    fn test_extraction_target() {
+        let root = Path::new("out");
+        let path = ConventionalPathBuf::new("Art\\Items\\GUN.FRM");
+        assert_eq!(
+            path.extraction_target(root).unwrap(),
+            root.join("art").join("items").join("gun.frm")
+        );
+        let evil = ConventionalPathBuf::new("../gun.frm");
+        assert_eq!(
+            evil.extraction_target(root).unwrap_err().reason,
+            UnsafeReason::ParentDir
+        );
+    }
+}
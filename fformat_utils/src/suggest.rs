@@ -0,0 +1,115 @@
+use crate::{make_path_conventional, PathIndex};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion<'a> {
+    pub path: &'a str,
+    pub same_dir: bool,
+    /// Edit distance between the file names.
+    pub distance: usize,
+}
+
+/// Closest existing paths for a `missing` one, best first.
+///
+/// Candidates in the same directory go first, then the rest, each ranked by the edit
+/// distance of the file name; names too far off are not suggested at all.
+pub fn suggest<'a, I: PathIndex + ?Sized>(
+    missing: &str,
+    index: &'a I,
+    limit: usize,
+) -> Vec<Suggestion<'a>> {
+    let missing = make_path_conventional(missing);
+    let (dir, name) = split_dir(&missing);
+    let max_distance = (name.chars().count() / 3).max(2);
+
+    let mut found: Vec<(Suggestion<'a>, usize)> = index
+        .paths()
+        .filter(|&path| path != missing)
+        .filter_map(|path| {
+            let (candidate_dir, candidate_name) = split_dir(path);
+            let distance = levenshtein(name, candidate_name);
+            if distance > max_distance {
+                return None;
+            }
+            let suggestion = Suggestion {
+                path,
+                same_dir: candidate_dir == dir,
+                distance,
+            };
+            Some((suggestion, levenshtein(dir, candidate_dir)))
+        })
+        .collect();
+    found.sort_by(|(a, a_dir), (b, b_dir)| {
+        b.same_dir
+            .cmp(&a.same_dir)
+            .then(a.distance.cmp(&b.distance))
+            .then(a_dir.cmp(b_dir))
+            .then(a.path.cmp(b.path))
+    });
+    found.truncate(limit);
+    found
+        .into_iter()
+        .map(|(suggestion, _)| suggestion)
+        .collect()
+}
+
+fn split_dir(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(pos) => (&path[..pos], &path[pos + 1..]),
+        None => ("", path),
+    }
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_ch) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_ch != b_ch);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::DirIndex;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("pistoll.frm", "pistol.frm"), 1);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let index = DirIndex::new([
+            "art/items/pistol.frm",
+            "art/items/pistol2.frm",
+            "art/items/rifle.frm",
+            "art/inven/pistol.frm",
+            "art/items/pistoll.fr0",
+        ]);
+        let found: Vec<_> = suggest("Art\\Items\\PISTOLL.FRM", &index, 3)
+            .into_iter()
+            .map(|suggestion| (suggestion.path, suggestion.same_dir))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("art/items/pistol.frm", true),
+                ("art/items/pistol2.frm", true),
+                ("art/items/pistoll.fr0", true),
+            ]
+        );
+        let found = suggest("art/misc/pistol.frm", &index, 2);
+        assert_eq!(found[0].path, "art/inven/pistol.frm");
+        assert!(!found[0].same_dir);
+        assert!(suggest("text/engl/game.msg", &index, 5).is_empty());
+    }
+}
@@ -0,0 +1,443 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+
+use crate::{dir::DirIndex, native::conventional_from_native_lossy};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Created(String),
+    Modified(String),
+    Removed(String),
+    Renamed {
+        from: String,
+        to: String,
+    },
+    /// The kernel queue overflowed and events were lost, call [`Watcher::rescan`].
+    Overflow,
+}
+
+struct RawEvent {
+    wd: WatchDescriptor,
+    mask: EventMask,
+    cookie: u32,
+    name: Option<OsString>,
+}
+
+/// Keeps a [`DirIndex`] in sync with mounted data folders through inotify. A path
+/// provided by several mounts stays in the index until the last of them loses it.
+pub struct Watcher {
+    inotify: Inotify,
+    mounts: Vec<PathBuf>,
+    // watched dir -> (mount, native dir)
+    dirs: HashMap<WatchDescriptor, (usize, PathBuf)>,
+    // indexed path -> mounts providing it
+    owners: HashMap<String, Vec<usize>>,
+    buffer: Vec<u8>,
+}
+
+/// A directory can vanish between its event and our look at it.
+fn skip_missing(res: io::Result<()>) -> io::Result<()> {
+    match res {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+impl Watcher {
+    pub fn new() -> io::Result<Self> {
+        Ok(Watcher {
+            inotify: Inotify::init()?,
+            mounts: Vec::new(),
+            dirs: HashMap::new(),
+            owners: HashMap::new(),
+            buffer: vec![0; 64 * 1024],
+        })
+    }
+
+    pub fn mounts(&self) -> &[PathBuf] {
+        &self.mounts
+    }
+
+    /// Watches `root` recursively and adds the files below it to `index`.
+    pub fn mount<P: Into<PathBuf>>(&mut self, root: P, index: &mut DirIndex) -> io::Result<()> {
+        let root = root.into();
+        self.mounts.push(root.clone());
+        let mount = self.mounts.len() - 1;
+        let mut found = Vec::new();
+        self.add_tree(mount, &root, &mut found)?;
+        for path in found {
+            self.provide(mount, path, index);
+        }
+        Ok(())
+    }
+
+    /// Walks every mount again and brings `index` up to date with what is there, e.g.
+    /// after [`Change::Overflow`].
+    pub fn rescan(&mut self, index: &mut DirIndex) -> io::Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        for mount in 0..self.mounts.len() {
+            let root = self.mounts[mount].clone();
+            let mut found = Vec::new();
+            skip_missing(self.add_tree(mount, &root, &mut found))?;
+            let found: HashSet<String> = found.into_iter().collect();
+            let mut gone: Vec<String> = self
+                .owners
+                .iter()
+                .filter(|(path, owners)| owners.contains(&mount) && !found.contains(*path))
+                .map(|(path, _)| path.clone())
+                .collect();
+            gone.sort();
+            let mut found: Vec<String> = found.into_iter().collect();
+            found.sort();
+            for path in found {
+                if let Some(path) = self.provide(mount, path, index) {
+                    changes.push(Change::Created(path));
+                }
+            }
+            for path in gone {
+                if self.withdraw(mount, &path, index) {
+                    changes.push(Change::Removed(path));
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Records that `mount` has `path`, returns it when it is new to `index`.
+    fn provide(&mut self, mount: usize, path: String, index: &mut DirIndex) -> Option<String> {
+        let owners = self.owners.entry(path.clone()).or_default();
+        if owners.contains(&mount) {
+            return None;
+        }
+        owners.push(mount);
+        if index.insert(&path) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Records that `mount` lost `path`, returns whether it left `index`.
+    fn withdraw(&mut self, mount: usize, path: &str, index: &mut DirIndex) -> bool {
+        let owners = match self.owners.get_mut(path) {
+            Some(owners) => owners,
+            None => return false,
+        };
+        owners.retain(|&owner| owner != mount);
+        if !owners.is_empty() {
+            return false;
+        }
+        self.owners.remove(path);
+        index.remove(path)
+    }
+
+    /// Applies pending events to `index` without blocking.
+    pub fn poll(&mut self, index: &mut DirIndex) -> io::Result<Vec<Change>> {
+        let events = match self.inotify.read_events(&mut self.buffer) {
+            Ok(events) => events.map(to_raw).collect(),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        self.apply(events, index)
+    }
+
+    /// Waits for at least one event and applies it to `index`.
+    pub fn wait(&mut self, index: &mut DirIndex) -> io::Result<Vec<Change>> {
+        let events = self
+            .inotify
+            .read_events_blocking(&mut self.buffer)?
+            .map(to_raw)
+            .collect();
+        self.apply(events, index)
+    }
+
+    fn apply(&mut self, events: Vec<RawEvent>, index: &mut DirIndex) -> io::Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        // cookie -> (path, whether it left the index)
+        let mut moved_from: HashMap<u32, (String, bool)> = HashMap::new();
+
+        for event in events {
+            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                changes.push(Change::Overflow);
+                continue;
+            }
+            // the kernel already dropped the watch of a deleted directory
+            if event
+                .mask
+                .intersects(EventMask::IGNORED | EventMask::DELETE_SELF)
+            {
+                self.dirs.remove(&event.wd);
+                continue;
+            }
+            let (mount, native) = match (self.dirs.get(&event.wd), &event.name) {
+                (Some((mount, dir)), Some(name)) => (*mount, dir.join(name)),
+                _ => continue,
+            };
+            let path = match conventional_from_native_lossy(&self.mounts[mount], &native) {
+                Ok(path) => path.into_string(),
+                Err(_) => continue,
+            };
+
+            if event.mask.contains(EventMask::ISDIR) {
+                if event
+                    .mask
+                    .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+                {
+                    let mut found = Vec::new();
+                    skip_missing(self.add_tree(mount, &native, &mut found))?;
+                    for path in found {
+                        if let Some(path) = self.provide(mount, path, index) {
+                            changes.push(Change::Created(path));
+                        }
+                    }
+                } else if event
+                    .mask
+                    .intersects(EventMask::DELETE | EventMask::MOVED_FROM)
+                {
+                    let below: Vec<WatchDescriptor> = self
+                        .dirs
+                        .iter()
+                        .filter(|(_, (_, dir))| dir.starts_with(&native))
+                        .map(|(wd, _)| wd.clone())
+                        .collect();
+                    for wd in below {
+                        self.dirs.remove(&wd);
+                        // one moved out of the tree would keep reporting to us
+                        if event.mask.contains(EventMask::MOVED_FROM) {
+                            let _ = self.inotify.watches().remove(wd);
+                        }
+                    }
+                    let gone: Vec<String> = index.walk(&path).map(str::to_owned).collect();
+                    for path in gone {
+                        if self.withdraw(mount, &path, index) {
+                            changes.push(Change::Removed(path));
+                        }
+                    }
+                }
+            } else if event.mask.contains(EventMask::CREATE) {
+                if let Some(path) = self.provide(mount, path, index) {
+                    changes.push(Change::Created(path));
+                }
+            } else if event.mask.contains(EventMask::CLOSE_WRITE) {
+                changes.push(Change::Modified(path));
+            } else if event.mask.contains(EventMask::DELETE) {
+                if self.withdraw(mount, &path, index) {
+                    changes.push(Change::Removed(path));
+                }
+            } else if event.mask.contains(EventMask::MOVED_FROM) {
+                let gone = self.withdraw(mount, &path, index);
+                moved_from.insert(event.cookie, (path, gone));
+            } else if event.mask.contains(EventMask::MOVED_TO) {
+                let to = self.provide(mount, path.clone(), index);
+                match (moved_from.remove(&event.cookie), to) {
+                    (Some((from, true)), Some(to)) => changes.push(Change::Renamed { from, to }),
+                    (from, to) => {
+                        if let Some((from, true)) = from {
+                            changes.push(Change::Removed(from));
+                        }
+                        changes.push(match to {
+                            Some(to) => Change::Created(to),
+                            None => Change::Modified(path),
+                        });
+                    }
+                }
+            }
+        }
+
+        // moved out of the watched tree
+        let mut moved_out: Vec<String> = moved_from
+            .into_values()
+            .filter(|(_, gone)| *gone)
+            .map(|(path, _)| path)
+            .collect();
+        moved_out.sort();
+        changes.extend(moved_out.into_iter().map(Change::Removed));
+        Ok(changes)
+    }
+
+    /// Watches `dir` and the directories below it, collecting the files in `found`.
+    fn add_tree(&mut self, mount: usize, dir: &Path, found: &mut Vec<String>) -> io::Result<()> {
+        let mask = WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::DELETE_SELF
+            | WatchMask::ONLYDIR
+            | WatchMask::DONT_FOLLOW;
+        let wd = self.inotify.watches().add(dir, mask)?;
+        self.dirs.insert(wd, (mount, dir.to_owned()));
+
+        let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let native = entry.path();
+            let is_dir = match entry.file_type() {
+                Ok(file_type) => file_type.is_dir(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            if is_dir {
+                skip_missing(self.add_tree(mount, &native, found))?;
+            } else if let Ok(path) = conventional_from_native_lossy(&self.mounts[mount], &native) {
+                found.push(path.into_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_raw(event: inotify::Event<&std::ffi::OsStr>) -> RawEvent {
+    RawEvent {
+        wd: event.wd,
+        mask: event.mask,
+        cookie: event.cookie,
+        name: event.name.map(ToOwned::to_owned),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::tests::TempRoot;
+
+    #[test]
+    fn test_watch() {
+        let root = TempRoot::new("watch");
+        fs::create_dir_all(root.join("Art")).unwrap();
+        fs::write(root.join("Art/GUN.FRM"), b"").unwrap();
+
+        let mut index = DirIndex::default();
+        let mut watcher = Watcher::new().unwrap();
+        watcher.mount(&*root, &mut index).unwrap();
+        assert!(index.contains("art/gun.frm"));
+
+        fs::write(root.join("Art/Knife.FRM"), b"").unwrap();
+        fs::rename(root.join("Art/GUN.FRM"), root.join("Art/Pistol.FRM")).unwrap();
+        fs::create_dir_all(root.join("Text/Engl")).unwrap();
+        fs::write(root.join("Text/Engl/Game.MSG"), b"").unwrap();
+        let mut changes = Vec::new();
+        for _ in 0..10 {
+            changes.extend(watcher.poll(&mut index).unwrap());
+            if index.contains("text/engl/game.msg") {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(changes.contains(&Change::Created("art/knife.frm".into())));
+        assert!(changes.contains(&Change::Renamed {
+            from: "art/gun.frm".into(),
+            to: "art/pistol.frm".into()
+        }));
+        assert!(index.contains("art/pistol.frm"));
+        assert!(!index.contains("art/gun.frm"));
+        assert!(index.contains("text/engl/game.msg"));
+
+        fs::remove_dir_all(root.join("Text")).unwrap();
+        fs::remove_file(root.join("Art/Knife.FRM")).unwrap();
+        let changes = watcher.poll(&mut index).unwrap();
+        assert!(changes.contains(&Change::Removed("art/knife.frm".into())));
+        assert!(!index.contains("text/engl/game.msg"));
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn test_moved_out() {
+        let root = TempRoot::new("moved_out");
+        let data = root.join("data");
+        fs::create_dir_all(data.join("Art/Items")).unwrap();
+        fs::write(data.join("Art/Items/Gun.frm"), b"").unwrap();
+
+        let mut index = DirIndex::default();
+        let mut watcher = Watcher::new().unwrap();
+        watcher.mount(&data, &mut index).unwrap();
+        let art: Vec<WatchDescriptor> = watcher
+            .dirs
+            .iter()
+            .filter(|(_, (_, dir))| dir.starts_with(data.join("Art")))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+        assert_eq!(art.len(), 2);
+
+        fs::rename(data.join("Art"), root.join("Art")).unwrap();
+        assert_eq!(
+            watcher.poll(&mut index).unwrap(),
+            [Change::Removed("art/items/gun.frm".into())]
+        );
+        assert_eq!(watcher.dirs.len(), 1);
+        for wd in art {
+            // already removed
+            assert!(watcher.inotify.watches().remove(wd).is_err());
+        }
+        fs::write(root.join("Art/Items/Knife.frm"), b"").unwrap();
+        assert!(watcher.poll(&mut index).unwrap().is_empty());
+
+        fs::remove_dir_all(&data).unwrap();
+        assert!(watcher.poll(&mut index).unwrap().is_empty());
+        assert!(watcher.dirs.is_empty());
+    }
+
+    #[test]
+    fn test_rescan_and_mounts() {
+        let root = TempRoot::new("rescan");
+        let (base, patch) = (root.join("base"), root.join("patch"));
+        for mount in [&base, &patch] {
+            fs::create_dir_all(mount.join("Art")).unwrap();
+            fs::write(mount.join("Art/Gun.frm"), b"").unwrap();
+        }
+        fs::write(base.join("Art/Knife.frm"), b"").unwrap();
+
+        let mut index = DirIndex::default();
+        let mut watcher = Watcher::new().unwrap();
+        watcher.mount(&base, &mut index).unwrap();
+        watcher.mount(&patch, &mut index).unwrap();
+        assert_eq!(index.len(), 2);
+
+        // still provided by the other mount
+        fs::remove_file(base.join("Art/Gun.frm")).unwrap();
+        // lost without its event, as on overflow
+        fs::remove_file(base.join("Art/Knife.frm")).unwrap();
+        fs::write(patch.join("Art/New.frm"), b"").unwrap();
+        let changes = watcher.rescan(&mut index).unwrap();
+        assert_eq!(
+            changes,
+            [
+                Change::Removed("art/knife.frm".into()),
+                Change::Created("art/new.frm".into())
+            ]
+        );
+        assert!(index.contains("art/gun.frm"));
+        // the queued events don't report these again
+        assert_eq!(
+            watcher.poll(&mut index).unwrap(),
+            [Change::Modified("art/new.frm".into())]
+        );
+
+        fs::remove_file(patch.join("Art/Gun.frm")).unwrap();
+        assert_eq!(
+            watcher.poll(&mut index).unwrap(),
+            [Change::Removed("art/gun.frm".into())]
+        );
+
+        // a directory gone before its event is handled
+        let wd = watcher.dirs.keys().next().unwrap().clone();
+        let changes = watcher
+            .apply(
+                vec![RawEvent {
+                    wd,
+                    mask: EventMask::CREATE | EventMask::ISDIR,
+                    cookie: 0,
+                    name: Some("Gone".into()),
+                }],
+                &mut index,
+            )
+            .unwrap();
+        assert!(changes.is_empty());
+    }
+}
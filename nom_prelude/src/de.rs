@@ -0,0 +1,720 @@
+use std::fmt;
+
+use serde::de::{
+    self, value::StrDeserializer, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess,
+    SeqAccess, Visitor,
+};
+
+use crate::document::{Document, Line, Section};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// 1-based, `None` when the position is unknown.
+    pub line: Option<usize>,
+    pub msg: String,
+}
+
+impl Error {
+    fn at(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.msg),
+            None => f.write_str(&self.msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error {
+            line: None,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+/// Sections map to fields of the top-level struct, keys to fields of the section structs.
+///
+/// Keys before the first section are matched when no section has the field's name.
+/// Repeated sections, repeated keys and indexed keys (`Item0`, `Item1`, ...) form
+/// sequences, a single value is split on whitespace when a sequence is expected, and
+/// `-` is `None`.
+pub fn from_str<'de, T: de::Deserialize<'de>>(text: &'de str) -> Result<T, Error> {
+    let doc = Document::parse(text).map_err(|msg| Error {
+        line: crate::error_line(&msg),
+        msg,
+    })?;
+    from_document(&doc)
+}
+
+pub fn from_document<'de, T: de::Deserialize<'de>>(doc: &Document<'de>) -> Result<T, Error> {
+    T::deserialize(DocumentDeserializer { doc })
+}
+
+struct DocumentDeserializer<'a, 'de> {
+    doc: &'a Document<'de>,
+}
+
+impl<'a, 'de> Deserializer<'de> for DocumentDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut names: Vec<&'de str> = Vec::new();
+        for section in self.doc.sections.iter().filter(|s| s.header.is_some()) {
+            if !names.contains(&section.name()) {
+                names.push(section.name());
+            }
+        }
+        let fields = names
+            .into_iter()
+            .map(|name| {
+                let sections = self.doc.named(name).collect();
+                (name, Field::Sections(sections))
+            })
+            .collect();
+        visitor.visit_map(FieldsAccess::new(fields))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let root = &self.doc.sections[0];
+        let fields = fields
+            .iter()
+            .filter_map(|&name| {
+                let sections: Vec<_> = self.doc.named(name).collect();
+                if !sections.is_empty() {
+                    return Some((name, Field::Sections(sections)));
+                }
+                let values = Values::collect(root, name);
+                if values.items.is_empty() {
+                    None
+                } else {
+                    Some((name, Field::Values(values)))
+                }
+            })
+            .collect();
+        visitor.visit_map(FieldsAccess::new(fields))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct enum identifier ignored_any
+    }
+}
+
+enum Field<'a, 'de> {
+    Sections(Vec<&'a Section<'de>>),
+    Values(Values<'de>),
+}
+
+struct FieldsAccess<'a, 'de> {
+    fields: std::vec::IntoIter<(&'de str, Field<'a, 'de>)>,
+    value: Option<Field<'a, 'de>>,
+}
+
+impl<'a, 'de> FieldsAccess<'a, 'de> {
+    fn new(fields: Vec<(&'de str, Field<'a, 'de>)>) -> Self {
+        FieldsAccess {
+            fields: fields.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'a, 'de> MapAccess<'de> for FieldsAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.fields.next() {
+            Some((name, field)) => {
+                self.value = Some(field);
+                let key: StrDeserializer<Error> = name.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, Error> {
+        match self.value.take() {
+            Some(Field::Sections(sections)) => {
+                let line = sections[0].line();
+                seed.deserialize(SectionsDeserializer { sections })
+                    .map_err(|err| err.at(line))
+            }
+            Some(Field::Values(values)) => {
+                let line = values.items[0].line;
+                seed.deserialize(values).map_err(|err| err.at(line))
+            }
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+struct SectionsDeserializer<'a, 'de> {
+    sections: Vec<&'a Section<'de>>,
+}
+
+impl<'a, 'de> SectionsDeserializer<'a, 'de> {
+    fn single(self) -> Result<SectionDeserializer<'a, 'de>, Error> {
+        match self.sections.as_slice() {
+            [section] => Ok(SectionDeserializer { section }),
+            [first, second, ..] => Err(Error {
+                line: Some(second.line()),
+                msg: format!("section [{}] is repeated", first.name()),
+            }),
+            [] => Err(de::Error::custom("section is missing")),
+        }
+    }
+}
+
+impl<'a, 'de> Deserializer<'de> for SectionsDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(ItemsAccess {
+            items: self
+                .sections
+                .into_iter()
+                .map(|section| (section.line(), SectionDeserializer { section }))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        })
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_struct(name, fields, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map enum
+        identifier ignored_any
+    }
+}
+
+struct SectionDeserializer<'a, 'de> {
+    section: &'a Section<'de>,
+}
+
+impl<'a, 'de> Deserializer<'de> for SectionDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut keys: Vec<&'de str> = Vec::new();
+        for (key, _, _) in self.section.entries() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        let fields = keys
+            .into_iter()
+            .map(|key| {
+                let items = self
+                    .section
+                    .entries()
+                    .filter(|(entry_key, _, _)| *entry_key == key)
+                    .map(|(_, value, line)| Item::new(value, line))
+                    .collect();
+                let values = Values {
+                    items,
+                    indexed: false,
+                };
+                (key, Field::Values(values))
+            })
+            .collect();
+        visitor.visit_map(FieldsAccess::new(fields))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let fields = fields
+            .iter()
+            .filter_map(|&name| {
+                let values = Values::collect(self.section, name);
+                if values.items.is_empty() {
+                    None
+                } else {
+                    Some((name, Field::Values(values)))
+                }
+            })
+            .collect();
+        visitor.visit_map(FieldsAccess::new(fields))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct enum identifier ignored_any
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Item<'de> {
+    value: &'de str,
+    line: usize,
+}
+
+impl<'de> Item<'de> {
+    fn new(value: &'de str, line: &Line<'de>) -> Self {
+        Item {
+            value,
+            line: line.line,
+        }
+    }
+}
+
+struct Values<'de> {
+    items: Vec<Item<'de>>,
+    indexed: bool,
+}
+
+impl<'de> Values<'de> {
+    /// `key` itself, or `key0`, `key1`, ... ordered by index.
+    fn collect(section: &Section<'de>, key: &str) -> Self {
+        let mut exact = Vec::new();
+        let mut indexed = Vec::new();
+        for (entry_key, value, line) in section.entries() {
+            if entry_key == key {
+                exact.push(Item::new(value, line));
+            } else if let Some(index) = entry_key
+                .strip_prefix(key)
+                .filter(|index| index.bytes().all(|byte| byte.is_ascii_digit()))
+                .and_then(|index| index.parse::<usize>().ok())
+            {
+                indexed.push((index, Item::new(value, line)));
+            }
+        }
+        if !exact.is_empty() || indexed.is_empty() {
+            return Values {
+                items: exact,
+                indexed: false,
+            };
+        }
+        indexed.sort_by_key(|(index, _)| *index);
+        Values {
+            items: indexed.into_iter().map(|(_, item)| item).collect(),
+            indexed: true,
+        }
+    }
+
+    fn single(self) -> Result<ValueDeserializer<'de>, Error> {
+        match self.items.as_slice() {
+            [item] if !self.indexed => Ok(ValueDeserializer { item: *item }),
+            [_, second, ..] => Err(Error {
+                line: Some(second.line),
+                msg: "key is repeated".to_owned(),
+            }),
+            _ => Err(Error {
+                line: self.items.first().map(|item| item.line),
+                msg: "expected a single value, found indexed keys".to_owned(),
+            }),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Values<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.items.as_slice() {
+            [item] if !self.indexed => ValueDeserializer { item: *item }.deserialize_seq(visitor),
+            _ => visitor.visit_seq(ItemsAccess {
+                items: self
+                    .items
+                    .into_iter()
+                    .map(|item| (item.line, ValueDeserializer { item }))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            }),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.items.as_slice() {
+            [item] if item.value == "-" => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_unit
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct ValueDeserializer<'de> {
+    item: Item<'de>,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn parse<T: std::str::FromStr>(&self, what: &str) -> Result<T, Error> {
+        self.item.value.parse().map_err(|_| Error {
+            line: Some(self.item.line),
+            msg: format!("expected {}, found {:?}", what, self.item.value),
+        })
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit(self.parse(stringify!($visit).trim_start_matches("visit_"))?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.item.value)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.item.value {
+            "1" | "true" | "True" | "TRUE" => visitor.visit_bool(true),
+            "0" | "false" | "False" | "FALSE" => visitor.visit_bool(false),
+            _ => Err(Error {
+                line: Some(self.item.line),
+                msg: format!("expected bool, found {:?}", self.item.value),
+            }),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.item.value == "-" {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.item.value.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(Error {
+                line: Some(self.item.line),
+                msg: format!("expected nothing, found {:?}", self.item.value),
+            })
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Whitespace separated, like `fixed_list_of_numbers`.
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let line = self.item.line;
+        visitor.visit_seq(ItemsAccess {
+            items: self
+                .item
+                .value
+                .split_whitespace()
+                .map(|value| {
+                    (
+                        line,
+                        ValueDeserializer {
+                            item: Item { value, line },
+                        },
+                    )
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let line = self.item.line;
+        let value: StrDeserializer<Error> = self.item.value.into_deserializer();
+        visitor.visit_enum(value).map_err(|err| err.at(line))
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct ItemsAccess<D> {
+    items: std::vec::IntoIter<(usize, D)>,
+}
+
+impl<'de, D: Deserializer<'de, Error = Error>> SeqAccess<'de> for ItemsAccess<D> {
+    type Error = Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Error> {
+        match self.items.next() {
+            Some((line, de)) => seed.deserialize(de).map(Some).map_err(|err| err.at(line)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Kind {
+        Scenery,
+        Item,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "PascalCase")]
+    struct Header {
+        version: u32,
+        max_hex_x: u16,
+        script_module: Option<String>,
+        day_time: Vec<i32>,
+        no_logout: bool,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "PascalCase")]
+    struct Object<'a> {
+        map_x: u16,
+        kind: Kind,
+        name: &'a str,
+        item: Vec<u32>,
+        lexems: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Map<'a> {
+        #[serde(rename = "Comment")]
+        comment: String,
+        #[serde(rename = "Header")]
+        header: Header,
+        #[serde(rename = "Object", borrow)]
+        objects: Vec<Object<'a>>,
+    }
+
+    const MAP: &str = "Comment = some map\n\
+        [Header]\n\
+        Version = 4\n\
+        MaxHexX 200\n\
+        ScriptModule = -\n\
+        DayTime = 300 600 1140 1380\n\
+        NoLogout = 1\n\
+        \n\
+        [Object]\n\
+        MapX = 10\n\
+        Kind = Scenery\n\
+        Name = Big rock\n\
+        Item1 = 20\n\
+        Item0 = 10\n\
+        [Object]\n\
+        # comment\n\
+        MapX = 12\n\
+        Kind = Item\n\
+        Name = Gun\n\
+        Item = 5\n\
+        Item = 6\n\
+        Lexems = $x\n";
+
+    #[test]
+    fn test_from_str() {
+        let map: Map = from_str(MAP).unwrap();
+        assert_eq!(
+            map,
+            Map {
+                comment: "some map".into(),
+                header: Header {
+                    version: 4,
+                    max_hex_x: 200,
+                    script_module: None,
+                    day_time: vec![300, 600, 1140, 1380],
+                    no_logout: true,
+                },
+                objects: vec![
+                    Object {
+                        map_x: 10,
+                        kind: Kind::Scenery,
+                        name: "Big rock",
+                        item: vec![10, 20],
+                        lexems: None,
+                    },
+                    Object {
+                        map_x: 12,
+                        kind: Kind::Item,
+                        name: "Gun",
+                        item: vec![5, 6],
+                        lexems: Some("$x".into()),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let text = MAP.replace("MapX = 12", "MapX = twelve");
+        let err = from_str::<Map>(&text).unwrap_err();
+        assert_eq!(err.line, Some(17));
+        assert!(err.msg.contains("twelve"), "{}", err);
+
+        let text = MAP.replace("Version = 4", "Version = 4\nVersion = 5");
+        let err = from_str::<Map>(&text).unwrap_err();
+        assert_eq!(err.line, Some(4));
+
+        let text = MAP.replace("Kind = Item", "Kind = Critter");
+        let err = from_str::<Map>(&text).unwrap_err();
+        assert_eq!(err.line, Some(18));
+
+        let text = MAP.replace("NoLogout = 1\n", "");
+        let err = from_str::<Map>(&text).unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(err.msg.contains("NoLogout"), "{}", err);
+    }
+
+    #[test]
+    fn test_map() {
+        use std::collections::BTreeMap;
+
+        let text = "[Options]\nA = 1\nB = 2\n[Other]\nC = 3\n";
+        let doc: BTreeMap<String, BTreeMap<String, String>> = from_str(text).unwrap();
+        assert_eq!(doc["Options"]["B"], "2");
+        assert_eq!(doc["Other"]["C"], "3");
+    }
+}
@@ -0,0 +1,342 @@
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::fmt;
+
+use crate::{
+    document::{entry_ids, ids, section_ids, Document, Id, Line},
+    msg::MsgTable,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// `index` counts equal names or numbers from 0, it is only set when they repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    Section {
+        name: &'a str,
+        index: Option<usize>,
+    },
+    /// `section` is `""` for the leading one.
+    Key {
+        section: &'a str,
+        index: Option<usize>,
+        key: &'a str,
+    },
+    Message {
+        number: u32,
+        index: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change<'a> {
+    pub kind: ChangeKind,
+    pub target: Target<'a>,
+    /// Values of keys and texts of messages.
+    pub old: Option<&'a str>,
+    pub new: Option<&'a str>,
+    /// 1-based, in the old and the new document.
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+}
+
+struct SectionName<'a>(&'a str, Option<usize>);
+
+impl fmt::Display for SectionName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0)?;
+        match self.1 {
+            Some(index) => write!(f, " #{}", index),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Shown<'a>(Option<&'a str>);
+
+impl fmt::Display for Shown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(value) if !value.is_empty() => f.write_str(value),
+            _ => f.write_str("\"\""),
+        }
+    }
+}
+
+/// `line 42: [Objects] #512: changed `MapX` from 10 to 12`
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, self.new_line, self.old_line) {
+            (ChangeKind::Removed, _, Some(line)) => write!(f, "old line {}: ", line)?,
+            (_, Some(line), _) => write!(f, "line {}: ", line)?,
+            _ => {}
+        }
+        let (old, new) = (Shown(self.old), Shown(self.new));
+        match self.target {
+            Target::Section { name, index } => {
+                write!(f, "{} {}", self.kind.as_str(), SectionName(name, index))
+            }
+            Target::Key {
+                section,
+                index,
+                key,
+            } => {
+                if !section.is_empty() || index.is_some() {
+                    write!(f, "{}: ", SectionName(section, index))?;
+                }
+                match self.kind {
+                    ChangeKind::Added => write!(f, "added `{}` = {}", key, new),
+                    ChangeKind::Removed => write!(f, "removed `{}` = {}", key, old),
+                    ChangeKind::Changed => write!(f, "changed `{}` from {} to {}", key, old, new),
+                }
+            }
+            Target::Message { number, index } => {
+                write!(f, "{} message {}", self.kind.as_str(), number)?;
+                if let Some(index) = index {
+                    write!(f, " #{}", index)?;
+                }
+                match self.kind {
+                    ChangeKind::Added => write!(f, ": {:?}", self.new.unwrap_or_default()),
+                    ChangeKind::Removed => write!(f, ": {:?}", self.old.unwrap_or_default()),
+                    ChangeKind::Changed => write!(
+                        f,
+                        " from {:?} to {:?}",
+                        self.old.unwrap_or_default(),
+                        self.new.unwrap_or_default()
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// Pairs up positions in `old` and `new` with equal ids in the order of `new`,
+/// unmatched old ones come before the next match that follows them.
+fn align<K: Ord + Copy>(
+    old: &[(Id<K>, usize)],
+    new: &[(Id<K>, usize)],
+) -> Vec<(Option<usize>, Option<usize>)> {
+    let new_ids: BTreeSet<Id<K>> = new.iter().map(|&(id, _)| id).collect();
+    let mut removed: Vec<bool> = old.iter().map(|(id, _)| !new_ids.contains(id)).collect();
+    let mut pairs = Vec::new();
+    for (n, &(id, _)) in new.iter().enumerate() {
+        match old.iter().position(|&(other, _)| other == id) {
+            Some(o) => {
+                for (earlier, removed) in removed[..o].iter_mut().enumerate() {
+                    if core::mem::take(removed) {
+                        pairs.push((Some(earlier), None));
+                    }
+                }
+                pairs.push((Some(o), Some(n)));
+            }
+            None => pairs.push((None, Some(n))),
+        }
+    }
+    for (o, removed) in removed.into_iter().enumerate() {
+        if removed {
+            pairs.push((Some(o), None));
+        }
+    }
+    pairs
+}
+
+/// Names occurring more than once on either side.
+fn repeated<K: Ord + Copy>(old: &[(Id<K>, usize)], new: &[(Id<K>, usize)]) -> BTreeSet<K> {
+    old.iter()
+        .chain(new)
+        .filter(|((_, occurrence), _)| *occurrence > 0)
+        .map(|((name, _), _)| *name)
+        .collect()
+}
+
+fn diff_entries<'a>(
+    section: &'a str,
+    index: Option<usize>,
+    old: &[Line<'a>],
+    new: &[Line<'a>],
+    changes: &mut Vec<Change<'a>>,
+) {
+    let (old_ids, new_ids) = (entry_ids(old), entry_ids(new));
+    for pair in align(&old_ids, &new_ids) {
+        let old_line = pair.0.map(|o| &old[old_ids[o].1]);
+        let new_line = pair.1.map(|n| &new[new_ids[n].1]);
+        let (old_entry, new_entry) = (
+            old_line.and_then(Line::entry),
+            new_line.and_then(Line::entry),
+        );
+        let kind = match (old_entry, new_entry) {
+            (Some((_, old)), Some((_, new))) if old == new => continue,
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (Some(_), None) => ChangeKind::Removed,
+            _ => ChangeKind::Added,
+        };
+        let key = new_entry.or(old_entry).map_or("", |(key, _)| key);
+        changes.push(Change {
+            kind,
+            target: Target::Key {
+                section,
+                index,
+                key,
+            },
+            old: old_entry.map(|(_, value)| value),
+            new: new_entry.map(|(_, value)| value),
+            old_line: old_line.map(|line| line.line),
+            new_line: new_line.map(|line| line.line),
+        });
+    }
+}
+
+/// Sections and keys that were added, removed or changed, in the order of `new`.
+/// Repeated sections are matched in order.
+pub fn diff_documents<'a>(old: &Document<'a>, new: &Document<'a>) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    diff_entries(
+        "",
+        None,
+        &old.sections[0].lines,
+        &new.sections[0].lines,
+        &mut changes,
+    );
+    let (old_ids, new_ids) = (section_ids(old), section_ids(new));
+    let repeated = repeated(&old_ids, &new_ids);
+    for pair in align(&old_ids, &new_ids) {
+        let ((name, occurrence), _) = pair
+            .1
+            .map(|n| new_ids[n])
+            .or(pair.0.map(|o| old_ids[o]))
+            .unwrap();
+        let index = Some(occurrence).filter(|_| repeated.contains(name));
+        let old_section = pair.0.map(|o| &old.sections[old_ids[o].1]);
+        let new_section = pair.1.map(|n| &new.sections[new_ids[n].1]);
+        match (old_section, new_section) {
+            (Some(old_section), Some(new_section)) => diff_entries(
+                name,
+                index,
+                &old_section.lines,
+                &new_section.lines,
+                &mut changes,
+            ),
+            (old_section, new_section) => changes.push(Change {
+                kind: match new_section {
+                    Some(_) => ChangeKind::Added,
+                    None => ChangeKind::Removed,
+                },
+                target: Target::Section { name, index },
+                old: None,
+                new: None,
+                old_line: old_section.map(|section| section.line()),
+                new_line: new_section.map(|section| section.line()),
+            }),
+        }
+    }
+    changes
+}
+
+/// Messages that were added, removed or changed, in the order of `new`. Repeated
+/// numbers are matched in order.
+pub fn diff_messages<'a>(old: &MsgTable<'a>, new: &MsgTable<'a>) -> Vec<Change<'a>> {
+    let id_list = |table: &MsgTable| {
+        ids(table
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| (message.number, index)))
+    };
+    let (old_ids, new_ids) = (id_list(old), id_list(new));
+    let repeated = repeated(&old_ids, &new_ids);
+    let mut changes = vec![];
+    for pair in align(&old_ids, &new_ids) {
+        let old_message = pair.0.map(|o| &old.messages[old_ids[o].1]);
+        let new_message = pair.1.map(|n| &new.messages[new_ids[n].1]);
+        let kind = match (old_message, new_message) {
+            (Some(old), Some(new)) if (old.audio, old.text) == (new.audio, new.text) => continue,
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (Some(_), None) => ChangeKind::Removed,
+            _ => ChangeKind::Added,
+        };
+        let ((number, occurrence), _) = pair
+            .1
+            .map(|n| new_ids[n])
+            .or(pair.0.map(|o| old_ids[o]))
+            .unwrap();
+        changes.push(Change {
+            kind,
+            target: Target::Message {
+                number,
+                index: Some(occurrence).filter(|_| repeated.contains(&number)),
+            },
+            old: old_message.map(|message| message.text),
+            new: new_message.map(|message| message.text),
+            old_line: old_message.map(|message| message.line),
+            new_line: new_message.map(|message| message.line),
+        });
+    }
+    changes
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_documents() {
+        let old = Document::parse(
+            "Version = 1\n[Header]\nMaxHexX = 200\nName = old\n[Objects]\nPid = 1\nMapX = 10\n[Objects]\nPid = 2\n[Tiles]\n",
+        )
+        .unwrap();
+        let new = Document::parse(
+            "Version = 1\n[Header]\nMaxHexX = 300\n[Objects]\nPid = 1\nMapX = 12\nMapY = 5\n[Objects]\nPid = 2\n[Objects]\nPid = 3\n",
+        )
+        .unwrap();
+        let changes: Vec<String> = diff_documents(&old, &new)
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "line 3: [Header]: changed `MaxHexX` from 200 to 300",
+                "old line 4: [Header]: removed `Name` = old",
+                "line 6: [Objects] #0: changed `MapX` from 10 to 12",
+                "line 7: [Objects] #0: added `MapY` = 5",
+                "line 10: added [Objects] #2",
+                "old line 10: removed [Tiles]",
+            ]
+        );
+        assert!(diff_documents(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_messages() {
+        let old =
+            MsgTable::parse("{100}{}{Hello}\n{101}{}{Bye}\n{102}{}{A}\n{102}{}{B}\n").unwrap();
+        let new =
+            MsgTable::parse("{100}{}{Hi}\n{102}{}{A}\n{102}{}{C}\n{103}{}{New\nline}\n").unwrap();
+        let changes = diff_messages(&old, &new);
+        let shown: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "line 1: changed message 100 from \"Hello\" to \"Hi\"",
+                "old line 2: removed message 101: \"Bye\"",
+                "line 3: changed message 102 #1 from \"B\" to \"C\"",
+                "line 4: added message 103: \"New\\nline\"",
+            ]
+        );
+        assert_eq!(changes[2].old_line, Some(4));
+    }
+}
@@ -0,0 +1,348 @@
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::fmt;
+
+use nom::{
+    bytes::complete::{tag, take_till, take_till1},
+    character::complete::{char, space0},
+    combinator::{all_consuming, map, opt, rest},
+    error::{ErrorKind, ParseError},
+    sequence::{delimited, pair, preceded, tuple},
+    IResult, Offset,
+};
+
+use crate::{any_line_ending, eof};
+
+/// How an entry separates its key from the value. Readers built from `kv` expect
+/// `Space`, those from `kv_sep` / `kv_eq` expect `Equals`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntrySeparator {
+    /// `Key = Value` or `Key=Value`.
+    Equals,
+    /// `Key Value`, or a lone `Key`.
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind<'a> {
+    Blank,
+    Comment(&'a str),
+    Section(&'a str),
+    Entry {
+        key: &'a str,
+        value: &'a str,
+        separator: EntrySeparator,
+    },
+}
+
+/// One physical line, `raw` and `eol` together are the exact source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+    pub kind: LineKind<'a>,
+    pub raw: &'a str,
+    pub eol: &'a str,
+    /// 1-based.
+    pub line: usize,
+    /// Byte offset of the line start.
+    pub offset: usize,
+}
+
+impl<'a> Line<'a> {
+    pub fn entry(&self) -> Option<(&'a str, &'a str)> {
+        match self.kind {
+            LineKind::Entry { key, value, .. } => Some((key, value)),
+            _ => None,
+        }
+    }
+}
+
+/// Lines from a `[Name]` header up to the next one, the leading section has no header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    pub header: Option<Line<'a>>,
+    pub lines: Vec<Line<'a>>,
+}
+
+impl<'a> Section<'a> {
+    /// `""` for the leading section.
+    pub fn name(&self) -> &'a str {
+        match self.header.map(|header| header.kind) {
+            Some(LineKind::Section(name)) => name,
+            _ => "",
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match (&self.header, self.lines.first()) {
+            (Some(header), _) => header.line,
+            (None, Some(first)) => first.line,
+            (None, None) => 1,
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&'a str, &'a str, &Line<'a>)> + '_ {
+        self.lines
+            .iter()
+            .filter_map(|line| line.entry().map(|(key, value)| (key, value, line)))
+    }
+
+    /// Last value of `key`, like the engine's config reader.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.entries()
+            .filter(|(entry_key, _, _)| *entry_key == key)
+            .map(|(_, value, _)| value)
+            .last()
+    }
+}
+
+/// `[Section]` / `Key = Value` text with comments and blank lines kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document<'a> {
+    pub sections: Vec<Section<'a>>,
+}
+
+impl<'a> Document<'a> {
+    #[cfg(feature = "std")]
+    pub fn parse(text: &'a str) -> Result<Self, String> {
+        crate::nom_err_to_string(
+            text,
+            all_consuming(document::<nom::error::VerboseError<&str>>)(text),
+        )
+        .map(|(_, doc)| doc)
+    }
+
+    pub fn named<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s Section<'a>> + 's {
+        self.sections
+            .iter()
+            .filter(move |section| section.header.is_some() && section.name() == name)
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section<'a>> {
+        self.sections
+            .iter()
+            .find(|section| section.header.is_some() && section.name() == name)
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &Line<'a>> + '_ {
+        self.sections
+            .iter()
+            .flat_map(|section| section.header.iter().chain(section.lines.iter()))
+    }
+}
+
+impl fmt::Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines() {
+            f.write_str(line.raw)?;
+            f.write_str(line.eol)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn comment<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    preceded(
+        space0,
+        preceded(
+            nom::branch::alt((tag("#"), tag(";"), tag("//"))),
+            map(rest, str::trim),
+        ),
+    )(i)
+}
+
+pub fn section_header<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    delimited(
+        pair(space0, char('[')),
+        map(take_till1(|ch| ch == ']'), str::trim),
+        pair(char(']'), space0),
+    )(i)
+}
+
+/// `Key = Value`, `Key=Value` or `Key Value`.
+pub fn entry<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (&'a str, &'a str, EntrySeparator), E> {
+    map(
+        tuple((
+            preceded(space0, take_till1(|ch| " \t=".contains(ch))),
+            space0,
+            opt(char('=')),
+            rest,
+        )),
+        |(key, _, equals, value): (&str, _, _, &str)| {
+            let separator = match equals {
+                Some(_) => EntrySeparator::Equals,
+                None => EntrySeparator::Space,
+            };
+            (key, value.trim(), separator)
+        },
+    )(i)
+}
+
+fn line_kind<'a, E: ParseError<&'a str>>(raw: &'a str) -> Result<LineKind<'a>, nom::Err<E>> {
+    if raw.trim().is_empty() {
+        return Ok(LineKind::Blank);
+    }
+    if let Ok((_, text)) = comment::<E>(raw) {
+        return Ok(LineKind::Comment(text));
+    }
+    if raw.trim_start().starts_with('[') {
+        let (_, name) = all_consuming(section_header)(raw)?;
+        return Ok(LineKind::Section(name));
+    }
+    let (_, (key, value, separator)) = entry(raw)?;
+    Ok(LineKind::Entry {
+        key,
+        value,
+        separator,
+    })
+}
+
+pub fn raw_line<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, (&'a str, &'a str), E> {
+    if i.is_empty() {
+        return Err(nom::Err::Error(E::from_error_kind(i, ErrorKind::Eof)));
+    }
+    pair(
+        take_till(|ch| ch == '\r' || ch == '\n'),
+        nom::branch::alt((any_line_ending, eof)),
+    )(i)
+}
+
+pub fn document<'a, E: ParseError<&'a str>>(text: &'a str) -> IResult<&'a str, Document<'a>, E> {
+    let mut sections = vec![Section {
+        header: None,
+        lines: Vec::new(),
+    }];
+    let mut i = text;
+    let mut number = 0;
+    while !i.is_empty() {
+        number += 1;
+        let offset = text.offset(i);
+        let (rest, (raw, eol)) = raw_line(i)?;
+        let kind = line_kind(raw).map_err(|err| match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                nom::Err::Failure(E::append(i, ErrorKind::Verify, e))
+            }
+            incomplete => incomplete,
+        })?;
+        let line = Line {
+            kind,
+            raw,
+            eol,
+            line: number,
+            offset,
+        };
+        match kind {
+            LineKind::Section(_) => sections.push(Section {
+                header: Some(line),
+                lines: Vec::new(),
+            }),
+            _ => sections.last_mut().unwrap().lines.push(line),
+        }
+        i = rest;
+    }
+    Ok((i, Document { sections }))
+}
+
+/// Line endings in `text`: `\n`, `\r\n` and a bare `\r`.
+pub(crate) fn line_endings(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    bytes
+        .iter()
+        .enumerate()
+        .filter(|&(pos, &byte)| {
+            byte == b'\n' || (byte == b'\r' && bytes.get(pos + 1) != Some(&b'\n'))
+        })
+        .count()
+}
+
+/// 1-based line of `rest` within `text`.
+pub fn line_of(text: &str, rest: &str) -> usize {
+    let offset = text.offset(rest).min(text.len());
+    let (before, after) = text.split_at(offset);
+    // `rest` starting inside a `\r\n` is still on the line that ends there
+    let split = before.ends_with('\r') && after.starts_with('\n');
+    line_endings(before) - split as usize + 1
+}
+
+/// A name, key or number and its occurrence among equal ones, so repeated sections,
+/// keys and messages are matched up in order.
+pub(crate) type Id<K> = (K, usize);
+
+pub(crate) fn ids<K: Ord + Copy, I: Iterator<Item = (K, usize)>>(items: I) -> Vec<(Id<K>, usize)> {
+    let mut seen: BTreeMap<K, usize> = BTreeMap::new();
+    items
+        .map(|(name, index)| {
+            let occurrence = seen.entry(name).or_insert(0);
+            *occurrence += 1;
+            ((name, *occurrence - 1), index)
+        })
+        .collect()
+}
+
+pub(crate) fn find<K: PartialEq>(ids: &[(Id<K>, usize)], id: Id<K>) -> Option<usize> {
+    ids.iter()
+        .find(|(other, _)| *other == id)
+        .map(|&(_, index)| index)
+}
+
+/// Entry ids with indices into `lines`.
+pub(crate) fn entry_ids<'a>(lines: &[Line<'a>]) -> Vec<(Id<&'a str>, usize)> {
+    ids(lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| line.entry().map(|(key, _)| (key, index))))
+}
+
+/// Ids of the sections after the leading one, with indices into `doc.sections`.
+pub(crate) fn section_ids<'a>(doc: &Document<'a>) -> Vec<(Id<&'a str>, usize)> {
+    ids(doc
+        .sections
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(index, section)| (section.name(), index)))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "# header comment\r\nVersion = 2\r\n\r\n[Header]\nMaxX=200\nName  Some name \n; note\n[Objects]\nMapX = 10\n[Objects]\nMapX = 12";
+
+    #[test]
+    fn test_document() {
+        let doc = Document::parse(TEXT).unwrap();
+        assert_eq!(doc.sections.len(), 4);
+        assert_eq!(doc.sections[0].name(), "");
+        assert_eq!(doc.sections[0].get("Version"), Some("2"));
+        let header = doc.section("Header").unwrap();
+        assert_eq!(header.line(), 4);
+        assert_eq!(header.get("MaxX"), Some("200"));
+        assert_eq!(header.get("Name"), Some("Some name"));
+        assert!(matches!(
+            header.lines[1].kind,
+            LineKind::Entry {
+                separator: EntrySeparator::Space,
+                ..
+            }
+        ));
+        assert_eq!(header.lines[2].kind, LineKind::Comment("note"));
+        assert_eq!(doc.named("Objects").count(), 2);
+        assert_eq!(doc.to_string(), TEXT);
+    }
+
+    #[test]
+    fn test_document_error() {
+        let err = Document::parse("[Header]\n[Broken\nKey = 1\n").unwrap_err();
+        assert!(err.contains("line 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_line_of() {
+        assert_eq!(line_of(TEXT, &TEXT[TEXT.find("MaxX").unwrap()..]), 5);
+        let text = "A = 1\rB = 2\r\nC = 3\nD";
+        let at = |part: &str| line_of(text, &text[text.find(part).unwrap()..]);
+        assert_eq!((at("A"), at("B"), at("C"), at("D")), (1, 2, 3, 4));
+        assert_eq!(at("\nC"), 2);
+    }
+}
@@ -0,0 +1,327 @@
+use alloc::{string::String, vec::Vec};
+use core::cmp::Ordering;
+
+use crate::{
+    document::{Document, EntrySeparator, Line, LineKind},
+    prepare::LineEnding,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `Key = Value`
+    Fixed,
+    /// `=` in one column within each section, `Key Value` entries keep one space.
+    Aligned,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub separator: Separator,
+    /// `None` keeps the most frequent ending of the input.
+    pub line_ending: Option<LineEnding>,
+    /// Blank lines before each section header.
+    pub section_spacing: usize,
+    /// Sorts keys within sections, see [`natural_cmp`]. Comments move with the entry
+    /// below them, repeated keys keep their order.
+    pub sort_keys: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            separator: Separator::Fixed,
+            line_ending: None,
+            section_spacing: 1,
+            sort_keys: false,
+        }
+    }
+}
+
+/// Case-insensitive, digit runs compared by value: `Key2` comes before `Key10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_end = a.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(a.len());
+                let b_end = b.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(b.len());
+                let (a_digits, b_digits) = (
+                    a[..a_end].trim_start_matches('0'),
+                    b[..b_end].trim_start_matches('0'),
+                );
+                let ord = a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a = &a[a_end..];
+                b = &b[b_end..];
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a = &a[x.len_utf8()..];
+                b = &b[y.len_utf8()..];
+            }
+            (x, y) => return x.is_some().cmp(&y.is_some()),
+        }
+    }
+}
+
+fn dominant_ending(doc: &Document) -> LineEnding {
+    let mut counts = [0usize; 3];
+    for line in doc.lines() {
+        match line.eol {
+            "\n" => counts[0] += 1,
+            "\r\n" => counts[1] += 1,
+            "\r" => counts[2] += 1,
+            _ => {}
+        }
+    }
+    // ties go to the earlier one
+    let most = counts
+        .iter()
+        .enumerate()
+        .max_by_key(|&(i, count)| (*count, 2 - i))
+        .map(|(i, _)| i);
+    match most {
+        Some(1) => LineEnding::CrLf,
+        Some(2) => LineEnding::Cr,
+        _ => LineEnding::Lf,
+    }
+}
+
+/// Body lines of a section without blank runs at either end and with inner runs
+/// collapsed to one, or sorted when asked.
+fn body<'d, 'a>(lines: &'d [Line<'a>], sort: bool) -> Vec<&'d Line<'a>> {
+    if sort {
+        // entries with the comments above them, blank lines are dropped
+        let mut units: Vec<(&str, Vec<&Line>)> = Vec::new();
+        let mut pending = Vec::new();
+        for line in lines {
+            match line.kind {
+                LineKind::Blank => {}
+                LineKind::Entry { key, .. } => {
+                    pending.push(line);
+                    units.push((key, core::mem::take(&mut pending)));
+                }
+                _ => pending.push(line),
+            }
+        }
+        units.sort_by(|a, b| natural_cmp(a.0, b.0));
+        return units
+            .into_iter()
+            .flat_map(|(_, lines)| lines)
+            .chain(pending)
+            .collect();
+    }
+    let mut body: Vec<&Line> = Vec::new();
+    for line in lines {
+        let blank = line.kind == LineKind::Blank;
+        let after_blank = body.last().is_none_or(|last| last.kind == LineKind::Blank);
+        if !(blank && after_blank) {
+            body.push(line);
+        }
+    }
+    if body.last().is_some_and(|last| last.kind == LineKind::Blank) {
+        body.pop();
+    }
+    body
+}
+
+/// Re-emits `doc` in canonical style. Comments are kept, and so is the meaning: the
+/// same sections with the same entries in the same order, unless sorted.
+pub fn format_document(doc: &Document, options: &FormatOptions) -> String {
+    let eol = options
+        .line_ending
+        .unwrap_or_else(|| dominant_ending(doc))
+        .as_str();
+    let mut out = String::new();
+    // comments right above a header belong to it
+    let mut glued: &[Line] = &[];
+    for (index, section) in doc.sections.iter().enumerate() {
+        let mut lines = &section.lines[..];
+        let mut next_glued: &[Line] = &[];
+        if index + 1 < doc.sections.len() {
+            let start = lines
+                .iter()
+                .rposition(|line| !matches!(line.kind, LineKind::Comment(_)))
+                .map_or(0, |pos| pos + 1);
+            next_glued = &lines[start..];
+            lines = &lines[..start];
+        }
+
+        if let Some(header) = &section.header {
+            if !out.is_empty() {
+                for _ in 0..options.section_spacing {
+                    out.push_str(eol);
+                }
+            }
+            for comment in glued {
+                out.push_str(comment.raw.trim());
+                out.push_str(eol);
+            }
+            match section.name() {
+                "" => out.push_str(header.raw.trim()),
+                name => {
+                    out.push('[');
+                    out.push_str(name);
+                    out.push(']');
+                }
+            }
+            out.push_str(eol);
+        }
+
+        let body = body(lines, options.sort_keys);
+        let width = match options.separator {
+            Separator::Fixed => 0,
+            Separator::Aligned => body
+                .iter()
+                .filter_map(|line| match line.kind {
+                    LineKind::Entry {
+                        key,
+                        separator: EntrySeparator::Equals,
+                        ..
+                    } => Some(key.chars().count()),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0),
+        };
+        for line in body {
+            match line.kind {
+                LineKind::Blank => {}
+                LineKind::Comment(_) => out.push_str(line.raw.trim()),
+                LineKind::Section(_) => unreachable!("headers start sections"),
+                LineKind::Entry {
+                    key,
+                    value,
+                    separator,
+                } => {
+                    out.push_str(key);
+                    if separator == EntrySeparator::Equals {
+                        for _ in key.chars().count()..width {
+                            out.push(' ');
+                        }
+                        out.push_str(" =");
+                    }
+                    if !value.is_empty() {
+                        out.push(' ');
+                        out.push_str(value);
+                    }
+                }
+            }
+            out.push_str(eol);
+        }
+        glued = next_glued;
+    }
+    out
+}
+
+#[cfg(feature = "std")]
+type Meaning<'a> = Vec<(Option<&'a str>, Vec<(&'a str, &'a str, EntrySeparator)>)>;
+
+/// Sections and their entries, what readers of a document see. The separator counts
+/// too: `kv` readers don't take `Key = Value`.
+#[cfg(feature = "std")]
+fn meaning<'a>(doc: &Document<'a>, sorted: bool) -> Meaning<'a> {
+    doc.sections
+        .iter()
+        .filter(|section| section.header.is_some() || section.entries().next().is_some())
+        .map(|section| {
+            let mut entries: Vec<_> = section
+                .lines
+                .iter()
+                .filter_map(|line| match line.kind {
+                    LineKind::Entry {
+                        key,
+                        value,
+                        separator,
+                    } => Some((key, value, separator)),
+                    _ => None,
+                })
+                .collect();
+            if sorted {
+                entries.sort_by(|a, b| natural_cmp(a.0, b.0));
+            }
+            (section.header.map(|_| section.name()), entries)
+        })
+        .collect()
+}
+
+/// Formats `text`, refusing if the result would read differently.
+#[cfg(feature = "std")]
+pub fn format(text: &str, options: &FormatOptions) -> Result<String, String> {
+    let doc = Document::parse(text)?;
+    let formatted = format_document(&doc, options);
+    let reparsed = Document::parse(&formatted)?;
+    if meaning(&reparsed, false) != meaning(&doc, options.sort_keys) {
+        return Err("formatting would change the meaning of the document".into());
+    }
+    Ok(formatted)
+}
+
+/// Check mode: whether `text` is already formatted.
+#[cfg(feature = "std")]
+pub fn is_formatted(text: &str, options: &FormatOptions) -> Result<bool, String> {
+    format(text, options).map(|formatted| formatted == text)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\r\n  # about\r\nVersion=2\r\n\r\n\r\n; header\r\n[ Header ]\r\n\r\nMaxHexX   200 \r\nName = Some name\n\r\n\r\n[Objects]\r\n# first\r\nMapY = 10\r\nMapX = 12\r\n\r\n# tail\r\n";
+
+    #[test]
+    fn test_format() {
+        let formatted = format(TEXT, &FormatOptions::default()).unwrap();
+        assert_eq!(
+            formatted,
+            "# about\r\nVersion = 2\r\n\r\n; header\r\n[Header]\r\nMaxHexX 200\r\nName = Some name\r\n\r\n[Objects]\r\n# first\r\nMapY = 10\r\nMapX = 12\r\n\r\n# tail\r\n"
+        );
+        assert_eq!(
+            is_formatted(&formatted, &FormatOptions::default()),
+            Ok(true)
+        );
+        assert_eq!(is_formatted(TEXT, &FormatOptions::default()), Ok(false));
+
+        let options = FormatOptions {
+            separator: Separator::Aligned,
+            line_ending: Some(LineEnding::Lf),
+            section_spacing: 0,
+            sort_keys: true,
+        };
+        assert_eq!(
+            format(TEXT, &options).unwrap(),
+            "# about\nVersion = 2\n; header\n[Header]\nMaxHexX 200\nName = Some name\n[Objects]\nMapX = 12\n# first\nMapY = 10\n# tail\n"
+        );
+        assert_eq!(format("", &options).unwrap(), "");
+        assert_eq!(format("K=\n[ ]\nV", &options).unwrap(), "K =\n[ ]\nV\n");
+        assert_eq!(
+            format(
+                "[Header]\nMapWidth\t200\nName=x\nScriptName = y\n",
+                &options
+            )
+            .unwrap(),
+            "[Header]\nMapWidth 200\nName       = x\nScriptName = y\n"
+        );
+        // what `kv` readers of map headers still take
+        let header = &formatted[formatted.find("MaxHexX").unwrap()..];
+        let (_, max_hex_x) =
+            crate::kv::<nom::error::VerboseError<&str>, u32, _>("MaxHexX", crate::integer)(header)
+                .unwrap();
+        assert_eq!(max_hex_x, 200);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut keys = ["Key10", "key2", "Key1", "Alpha", "Key02", "Key"];
+        keys.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(keys, ["Alpha", "Key", "Key1", "key2", "Key02", "Key10"]);
+    }
+}
@@ -1,3 +1,7 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub use nom::{
     self,
     branch::alt,
@@ -5,14 +9,14 @@ pub use nom::{
     combinator::{cond, cut, map, map_opt, map_parser, map_res, opt, peek, recognize, value},
     do_parse,
     error::{ErrorKind, ParseError},
-    multi::{count, fold_many0, fold_many_m_n, many0, many_m_n, separated_list},
+    multi::{fold_many0, fold_many_m_n},
     sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
-use nom::{
-    error::VerboseError, AsChar, Compare, InputIter, InputLength, InputTake, InputTakeAtPosition,
-    Offset, Slice,
-};
+// nom 5 only has the allocating combinators with its `std` feature
+#[cfg(feature = "std")]
+pub use nom::multi::{count, many0, many_m_n, separated_list};
+use nom::{AsChar, Compare, InputIter, InputLength, InputTake, InputTakeAtPosition, Offset, Slice};
 pub mod complete {
     pub use nom::{
         bytes::complete::{tag, take_till, take_till1, take_while1},
@@ -22,8 +26,27 @@ pub mod complete {
         },
     };
 }
-use std::ops::{Range, RangeFrom, RangeTo};
-pub use std::str::FromStr;
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::ops::{Range, RangeFrom, RangeTo};
+pub use core::str::FromStr;
+#[cfg(feature = "std")]
+use nom::error::VerboseError;
+
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod diff;
+pub mod document;
+pub mod format;
+pub mod merge;
+pub mod msg;
+pub mod prepare;
+pub mod reparse;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "trace")]
+pub mod trace;
 
 pub use arrayvec::ArrayVec;
 use complete::*;
@@ -46,13 +69,14 @@ pub trait StringLikeInput:
     fn trim(self) -> Self;
     // TODO: use FromExternalError from nom 0.6+
     fn parse<T: FromStr>(self) -> Result<T, Self::ParseError>;
+    #[cfg(feature = "std")]
     fn err_to_string<T>(
         self,
         res: IResult<Self, T, VerboseError<Self>>,
     ) -> Result<(Self, T), String>;
 }
 
-impl<'a> StringLikeInput for &'a [u8] {
+impl StringLikeInput for &[u8] {
     type Char = u8;
     type ParseError = ();
 
@@ -76,10 +100,11 @@ impl<'a> StringLikeInput for &'a [u8] {
     }
 
     fn parse<T: FromStr>(self) -> Result<T, Self::ParseError> {
-        let str = std::str::from_utf8(self).ok().ok_or(())?;
+        let str = core::str::from_utf8(self).ok().ok_or(())?;
         FromStr::from_str(str).ok().ok_or(())
     }
 
+    #[cfg(feature = "std")]
     fn err_to_string<T>(
         self,
         res: IResult<Self, T, VerboseError<Self>>,
@@ -87,7 +112,7 @@ impl<'a> StringLikeInput for &'a [u8] {
         nom_err_to_string_bytes(self, res)
     }
 }
-impl<'a> StringLikeInput for &'a str {
+impl StringLikeInput for &str {
     type Char = char;
     type ParseError = ();
 
@@ -99,6 +124,7 @@ impl<'a> StringLikeInput for &'a str {
         FromStr::from_str(self).ok().ok_or(())
     }
 
+    #[cfg(feature = "std")]
     fn err_to_string<T>(
         self,
         res: IResult<Self, T, VerboseError<Self>>,
@@ -107,6 +133,30 @@ impl<'a> StringLikeInput for &'a str {
     }
 }
 
+#[cfg(feature = "trace")]
+pub use trace::traced;
+
+/// Inputs [`traced`] takes. With the `trace` feature their length gives positions,
+/// without it any input will do.
+#[cfg(feature = "trace")]
+pub trait TraceInput: InputLength {}
+#[cfg(feature = "trace")]
+impl<I: InputLength> TraceInput for I {}
+#[cfg(not(feature = "trace"))]
+pub trait TraceInput {}
+#[cfg(not(feature = "trace"))]
+impl<I> TraceInput for I {}
+
+/// `parser` itself, the `trace` feature records its calls, see `trace::trace`.
+#[cfg(not(feature = "trace"))]
+#[inline(always)]
+pub fn traced<I, O, E, F>(_name: &'static str, parser: F) -> impl Fn(I) -> IResult<I, O, E>
+where
+    F: Fn(I) -> IResult<I, O, E>,
+{
+    parser
+}
+
 #[allow(dead_code)]
 pub fn slice_has_none<T>(slice: &[Option<T>]) -> bool {
     slice.iter().all(|item| item.is_none())
@@ -169,7 +219,7 @@ pub fn word<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str,
 pub fn line<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     let (rest, (line, _)) = pair(
         take_till1(|ch| "\r\n".contains(ch)),
-        alt((eof, line_ending)),
+        alt((eof, any_line_ending)),
     )(i)?;
     Ok((rest, line.trim()))
 }
@@ -201,18 +251,23 @@ pub fn int_bool<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, bool
 pub fn fixed_list_of_numbers<'a, E: ParseError<&'a str>, T: FromStr>(
     len: usize,
 ) -> impl Fn(&'a str) -> IResult<&'a str, Vec<T>, E> {
-    move |i| count(space0_number, len)(i)
+    move |i| count_cap(space0_number, len)(i)
 }
 
 pub fn t_rn<T, E: ParseError<T>>(i: T) -> IResult<T, T, E>
 where
     T: StringLikeInput,
 {
-    recognize(pair(space0, line_ending))(i)
+    recognize(pair(space0, any_line_ending))(i)
 }
 
 pub fn end_of_line<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
-    recognize(pair(space0, alt((line_ending, eof))))(i)
+    recognize(pair(space0, alt((any_line_ending, eof))))(i)
+}
+
+/// `\r\n`, `\n` or a bare `\r` of old Mac editors.
+pub fn any_line_ending<T: StringLikeInput, E: ParseError<T>>(i: T) -> IResult<T, T, E> {
+    alt((tag("\r\n"), tag("\n"), tag("\r")))(i)
 }
 
 pub fn section<'a, E: ParseError<&'a str>>(
@@ -271,7 +326,7 @@ pub fn cut_apply<T: StringLikeInput, E: ParseError<T>, O, F>(
 where
     F: Fn(T) -> IResult<T, O, E>,
 {
-    let (left, res) = cut(parser)(*i)?;
+    let (left, res) = traced("cut_apply", cut(parser))(*i)?;
     *i = left;
     Ok(res)
 }
@@ -571,6 +626,18 @@ pub fn err_to_kind<I, O>(res: IResult<I, O, (I, ErrorKind)>) -> Result<O, ErrorK
     }
 }
 
+/// 1-based line of the last `at line N` in a [`nom_err_to_string`] message.
+pub fn error_line(msg: &str) -> Option<usize> {
+    msg.rmatch_indices("at line ").find_map(|(pos, marker)| {
+        let rest = &msg[pos + marker.len()..];
+        let end = rest
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    })
+}
+
+#[cfg(feature = "std")]
 pub fn nom_err_to_string<'a, O>(
     text: &'a str,
     res: IResult<&'a str, O, VerboseError<&'a str>>,
@@ -590,6 +657,7 @@ pub fn nom_err_to_string<'a, O>(
     }
 }
 
+#[cfg(feature = "std")]
 pub fn nom_err_to_string_bytes<'a, O>(
     bytes: &'a [u8],
     res: IResult<&'a [u8], O, VerboseError<&'a [u8]>>,
@@ -645,12 +713,13 @@ pub fn separated_list_first_unchecked<I, O, O2, E, F, G>(
     f: F,
 ) -> impl Fn(I) -> IResult<I, Vec<O>, E>
 where
-    I: Clone + PartialEq,
+    I: Clone + PartialEq + TraceInput,
     F: Fn(I) -> IResult<I, O, E>,
     G: Fn(I) -> IResult<I, O2, E>,
     E: ParseError<I>,
 {
-    move |i: I| {
+    let (sep, f) = (traced("separator", sep), traced("item", f));
+    traced("separated_list_first_unchecked", move |i: I| {
         let mut res = Vec::new();
         let mut i = i.clone();
 
@@ -698,7 +767,7 @@ where
                 }
             }
         }
-    }
+    })
 }
 
 pub fn cond_err<I: Clone, O, E: ParseError<I>, F>(b: bool, f: F) -> impl Fn(I) -> IResult<I, O, E>
@@ -720,11 +789,22 @@ where
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use nom::error::VerboseError;
 
     use super::*;
+    #[test]
+    fn test_error_line() {
+        assert_eq!(error_line("Error: 0: at line 12:\nKey = 1"), Some(12));
+        assert_eq!(
+            error_line("expected a newline 5 at line 3, in Verify:\nline 9"),
+            Some(3)
+        );
+        assert_eq!(error_line("Error: no newline at the end"), None);
+        assert_eq!(error_line("at line 7"), Some(7));
+    }
+
     #[test]
     fn test_idigit1() {
         let parser = idigit1::<VerboseError<&str>>;
@@ -743,5 +823,7 @@ mod tests {
         let parser = t_rn::<_, VerboseError<&str>>;
         assert_eq!(Ok(("", "\n")), parser("\n"));
         assert_eq!(Ok(("", "\r\n")), parser("\r\n"));
+        assert_eq!(Ok(("x", " \r")), parser(" \rx"));
+        assert_eq!(Ok(("x", "a")), line::<VerboseError<&str>>("a\rx"));
     }
 }
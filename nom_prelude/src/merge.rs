@@ -0,0 +1,711 @@
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::{fmt, ops::Range};
+
+use crate::document::{entry_ids, find, section_ids, Document, Id, Line, LineKind, Section};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeOptions {
+    /// Length of the `<<<<<<<` markers, git's `%L`.
+    pub marker_size: usize,
+    pub ours_label: String,
+    pub theirs_label: String,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            marker_size: 7,
+            ours_label: "ours".into(),
+            theirs_label: "theirs".into(),
+        }
+    }
+}
+
+/// Something both sides changed differently, marked in the merged text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// 1-based line of the opening marker.
+    pub line: usize,
+    /// `""` for the leading section.
+    pub section: String,
+    /// `None` when the whole section conflicts.
+    pub key: Option<String>,
+    /// Value of the key or text of the section, `None` where that side deleted it.
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: [{}]", self.line, self.section)?;
+        if let Some(key) = &self.key {
+            write!(f, " {}", key)?;
+        }
+        match (&self.ours, &self.theirs) {
+            (None, _) => f.write_str(" deleted by ours, changed by theirs"),
+            (_, None) => f.write_str(" changed by ours, deleted by theirs"),
+            _ => f.write_str(" changed on both sides"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub text: String,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merged {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+fn value<'a>(line: &Line<'a>) -> &'a str {
+    line.entry().map_or("", |(_, value)| value)
+}
+
+fn same(a: &Section, b: &Section) -> bool {
+    a.entries()
+        .map(|(key, value, _)| (key, value))
+        .eq(b.entries().map(|(key, value, _)| (key, value)))
+}
+
+/// At least half of the entries of the longer one are in the other one too.
+fn similar(a: &Section, b: &Section) -> bool {
+    let shared = a
+        .entries()
+        .filter(|&(key, value, _)| b.entries().any(|(k, v, _)| (k, v) == (key, value)))
+        .count();
+    shared > 0 && shared * 2 >= a.entries().count().max(b.entries().count())
+}
+
+/// Where a repeated section of one side comes from in base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Origin {
+    /// Its occurrence in base.
+    Base(usize),
+    New,
+    /// Similar to more than one of these occurrences, or one of them is similar to
+    /// more than one section of the side.
+    Ambiguous(Range<usize>),
+}
+
+/// Origins of the sections of one side with the same repeated name. Unchanged sections
+/// are aligned by a longest common subsequence, a changed one between them must be
+/// the only one similar to the only similar section of base there.
+fn origins(base: &[&Section], side: &[&Section]) -> Vec<Origin> {
+    let prefix = base
+        .iter()
+        .zip(side)
+        .take_while(|(b, s)| same(b, s))
+        .count();
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(side[prefix..].iter().rev())
+        .take_while(|(b, s)| same(b, s))
+        .count();
+    let (n, m) = (base.len() - suffix, side.len() - suffix);
+    let width = m - prefix + 1;
+    // lengths of the common subsequences of the rests after `i` and `j`
+    let mut lengths = vec![0u32; (n - prefix + 1) * width];
+    let at = |i: usize, j: usize| (i - prefix) * width + j - prefix;
+    for i in (prefix..n).rev() {
+        for j in (prefix..m).rev() {
+            lengths[at(i, j)] = if same(base[i], side[j]) {
+                lengths[at(i + 1, j + 1)] + 1
+            } else {
+                lengths[at(i + 1, j)].max(lengths[at(i, j + 1)])
+            };
+        }
+    }
+    let mut anchors: Vec<(usize, usize)> = (0..prefix).map(|k| (k, k)).collect();
+    let (mut i, mut j) = (prefix, prefix);
+    while i < n && j < m {
+        if same(base[i], side[j]) && lengths[at(i, j)] == lengths[at(i + 1, j + 1)] + 1 {
+            anchors.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[at(i + 1, j)] >= lengths[at(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    anchors.extend((0..suffix).map(|k| (n + k, m + k)));
+
+    let mut origins = vec![Origin::New; side.len()];
+    let (mut from_base, mut from_side) = (0, 0);
+    for (anchor_base, anchor_side) in anchors
+        .into_iter()
+        .chain(core::iter::once((base.len(), side.len())))
+    {
+        let gap = from_base..anchor_base;
+        for j in from_side..anchor_side {
+            let candidates: Vec<usize> =
+                gap.clone().filter(|&i| similar(base[i], side[j])).collect();
+            origins[j] = match candidates[..] {
+                [] => Origin::New,
+                [i] if (from_side..anchor_side)
+                    .filter(|&other| similar(base[i], side[other]))
+                    .count()
+                    == 1 =>
+                {
+                    Origin::Base(i)
+                }
+                _ => Origin::Ambiguous(gap.clone()),
+            };
+        }
+        if anchor_side < side.len() {
+            origins[anchor_side] = Origin::Base(anchor_base);
+        }
+        (from_base, from_side) = (anchor_base + 1, anchor_side + 1);
+    }
+    origins
+}
+
+type SectionIds<'a> = Vec<(Id<&'a str>, usize)>;
+
+/// Section ids of `side` with the occurrences of repeated sections taken from the base
+/// sections they come from, new ones numbered after all of base. Also the sections
+/// whose origin is ambiguous.
+fn side_ids<'a>(
+    base: &Document<'a>,
+    b: &SectionIds<'a>,
+    side: &Document<'a>,
+    repeated: &BTreeSet<&str>,
+) -> (SectionIds<'a>, BTreeMap<usize, Range<usize>>) {
+    let mut ids = section_ids(side);
+    let mut ambiguous = BTreeMap::new();
+    for &name in repeated {
+        let base_sections: Vec<&Section> = b
+            .iter()
+            .filter(|((other, _), _)| *other == name)
+            .map(|&(_, index)| &base.sections[index])
+            .collect();
+        let positions: Vec<usize> = (0..ids.len())
+            .filter(|&pos| ids[pos].0 .0 == name)
+            .collect();
+        let side_sections: Vec<&Section> = positions
+            .iter()
+            .map(|&pos| &side.sections[ids[pos].1])
+            .collect();
+        let mut fresh = base_sections.len();
+        for (pos, origin) in positions
+            .into_iter()
+            .zip(origins(&base_sections, &side_sections))
+        {
+            ids[pos].0 .1 = match origin {
+                Origin::Base(occurrence) => occurrence,
+                Origin::New => {
+                    fresh += 1;
+                    fresh - 1
+                }
+                Origin::Ambiguous(range) => {
+                    ambiguous.insert(ids[pos].1, range);
+                    fresh += 1;
+                    fresh - 1
+                }
+            };
+        }
+    }
+    (ids, ambiguous)
+}
+
+/// The other side kept these occurrences of `name` in base as they were.
+fn untouched(
+    base: &Document,
+    b: &SectionIds,
+    other: &Document,
+    o: &SectionIds,
+    name: &str,
+    occurrences: &Range<usize>,
+) -> bool {
+    occurrences.clone().all(|occurrence| {
+        let base_section = find(b, (name, occurrence)).map(|index| &base.sections[index]);
+        let other_section = find(o, (name, occurrence)).map(|index| &other.sections[index]);
+        matches!((base_section, other_section), (Some(b), Some(o)) if same(b, o))
+    })
+}
+
+fn section_lines<'a>(section: &Section<'a>) -> Vec<Line<'a>> {
+    section
+        .header
+        .iter()
+        .chain(&section.lines)
+        .copied()
+        .collect()
+}
+
+fn section_text(section: &Section) -> String {
+    let mut text = String::new();
+    for line in section.header.iter().chain(&section.lines) {
+        text.push_str(line.raw);
+        text.push_str(line.eol);
+    }
+    text
+}
+
+struct Merger<'o> {
+    options: &'o MergeOptions,
+    /// Ending for added lines, the first one of ours.
+    eol: &'o str,
+    text: String,
+    lines: usize,
+    /// The last line has no ending yet.
+    open: bool,
+    /// Blank lines at the end of the text.
+    blanks: usize,
+    conflicts: Vec<Conflict>,
+}
+
+impl<'o> Merger<'o> {
+    fn push(&mut self, raw: &str, eol: &str) {
+        if self.open {
+            self.text.push_str(self.eol);
+        }
+        self.text.push_str(raw);
+        self.text.push_str(eol);
+        self.open = eol.is_empty();
+        self.lines += 1;
+        if raw.trim().is_empty() {
+            self.blanks += 1;
+        } else {
+            self.blanks = 0;
+        }
+    }
+
+    fn line(&mut self, line: &Line) {
+        self.push(line.raw, line.eol);
+    }
+
+    /// Line of ours with the value of theirs, keeping the spacing of ours.
+    fn replaced(&mut self, ours: &Line, theirs: &Line) {
+        let value = value(ours);
+        if value.is_empty() {
+            return self.push(theirs.raw, ours.eol);
+        }
+        let start = value.as_ptr() as usize - ours.raw.as_ptr() as usize;
+        let raw = format!(
+            "{}{}{}",
+            &ours.raw[..start],
+            self::value(theirs),
+            &ours.raw[start + value.len()..]
+        );
+        self.push(&raw, ours.eol);
+    }
+
+    fn marker(&mut self, ch: char, label: &str) {
+        let mut marker: String = core::iter::repeat_n(ch, self.options.marker_size).collect();
+        if !label.is_empty() {
+            marker.push(' ');
+            marker.push_str(label);
+        }
+        let eol = self.eol;
+        self.push(&marker, eol);
+    }
+
+    fn conflict(
+        &mut self,
+        section: &str,
+        key: Option<&str>,
+        ours: Option<(String, &[Line])>,
+        theirs: Option<(String, &[Line])>,
+    ) {
+        self.conflicts.push(Conflict {
+            line: self.lines + 1 + self.open as usize,
+            section: section.into(),
+            key: key.map(String::from),
+            ours: ours.as_ref().map(|(text, _)| text.clone()),
+            theirs: theirs.as_ref().map(|(text, _)| text.clone()),
+        });
+        let options = self.options;
+        self.marker('<', &options.ours_label);
+        for line in ours.iter().flat_map(|(_, lines)| lines.iter()) {
+            let eol = if line.eol.is_empty() {
+                self.eol
+            } else {
+                line.eol
+            };
+            self.push(line.raw, eol);
+        }
+        self.marker('=', "");
+        for line in theirs.iter().flat_map(|(_, lines)| lines.iter()) {
+            let eol = if line.eol.is_empty() {
+                self.eol
+            } else {
+                line.eol
+            };
+            self.push(line.raw, eol);
+        }
+        self.marker('>', &options.theirs_label);
+    }
+
+    fn section_conflict(&mut self, name: &str, ours: Option<&Section>, theirs: Option<&Section>) {
+        let (ours_lines, theirs_lines) = (ours.map(section_lines), theirs.map(section_lines));
+        self.conflict(
+            name,
+            None,
+            ours.map(section_text).zip(ours_lines.as_deref()),
+            theirs.map(section_text).zip(theirs_lines.as_deref()),
+        );
+    }
+
+    /// Merges the entries of one section into the lines of ours.
+    fn lines(&mut self, name: &str, base: &[Line], ours: &[Line], theirs: &[Line]) {
+        let (b, o, t) = (entry_ids(base), entry_ids(ours), entry_ids(theirs));
+
+        // theirs-only entries, placed after the entry of ours they follow in theirs
+        let first_entry = o.first().map_or(ours.len(), |&(_, index)| index);
+        let mut inserts: Vec<(usize, &Line, Option<&Line>)> = Vec::new();
+        for (pos, &(id, index)) in t.iter().enumerate() {
+            if find(&o, id).is_some() {
+                continue;
+            }
+            let line = &theirs[index];
+            let base_line = find(&b, id).map(|index| &base[index]);
+            if base_line.is_some_and(|base_line| value(base_line) == value(line)) {
+                // deleted by ours
+                continue;
+            }
+            let before = t[..pos]
+                .iter()
+                .rev()
+                .find_map(|&(id, _)| find(&o, id))
+                .map_or(first_entry, |index| index + 1);
+            inserts.push((before, line, base_line));
+        }
+
+        for index in 0..=ours.len() {
+            for &(_, line, base_line) in inserts.iter().filter(|insert| insert.0 == index) {
+                match base_line {
+                    None => self.push(
+                        line.raw,
+                        if line.eol.is_empty() {
+                            self.eol
+                        } else {
+                            line.eol
+                        },
+                    ),
+                    Some(_) => {
+                        let key = line.entry().map(|(key, _)| key);
+                        self.conflict(
+                            name,
+                            key,
+                            None,
+                            Some((value(line).into(), core::slice::from_ref(line))),
+                        )
+                    }
+                }
+            }
+            let line = match ours.get(index) {
+                Some(line) => line,
+                None => break,
+            };
+            let key = match line.entry() {
+                Some((key, _)) => key,
+                None => {
+                    self.line(line);
+                    continue;
+                }
+            };
+            let id = o
+                .iter()
+                .find(|&&(_, i)| i == index)
+                .map(|&(id, _)| id)
+                .unwrap();
+            let ours_value = value(line);
+            let base_value = find(&b, id).map(|index| value(&base[index]));
+            let theirs_line = find(&t, id).map(|index| &theirs[index]);
+            let theirs_value = theirs_line.map(value);
+            if theirs_value == Some(ours_value) || theirs_value == base_value {
+                self.line(line);
+            } else if base_value == Some(ours_value) {
+                if let Some(theirs_line) = theirs_line {
+                    self.replaced(line, theirs_line);
+                }
+            } else {
+                self.conflict(
+                    name,
+                    Some(key),
+                    Some((ours_value.into(), core::slice::from_ref(line))),
+                    theirs_line.map(|line| (value(line).into(), core::slice::from_ref(line))),
+                );
+            }
+        }
+    }
+
+    /// A section of theirs, after as many blank lines as it follows in theirs.
+    fn inserted(&mut self, section: &Section, before: &Section) {
+        let spacing = before
+            .lines
+            .iter()
+            .rev()
+            .take_while(|line| matches!(line.kind, LineKind::Blank))
+            .count();
+        if self.lines > 0 {
+            for _ in self.blanks..spacing {
+                let eol = self.eol;
+                self.push("", eol);
+            }
+        }
+        self.section(section);
+    }
+
+    fn section(&mut self, section: &Section) {
+        for line in section.header.iter().chain(&section.lines) {
+            self.line(line);
+        }
+    }
+}
+
+/// Three-way merge by section and key. The result keeps the lines of `ours` and
+/// takes over what only `theirs` changed; what both changed differently is left
+/// between conflict markers. Repeated sections are matched to base by their entries,
+/// one that could come from several sections of base conflicts.
+pub fn merge_documents(
+    base: &Document,
+    ours: &Document,
+    theirs: &Document,
+    options: &MergeOptions,
+) -> Merged {
+    let eol = ours
+        .lines()
+        .map(|line| line.eol)
+        .find(|eol| !eol.is_empty())
+        .unwrap_or("\n");
+    let mut merger = Merger {
+        options,
+        eol,
+        text: String::new(),
+        lines: 0,
+        open: false,
+        blanks: 0,
+        conflicts: Vec::new(),
+    };
+    merger.lines(
+        "",
+        &base.sections[0].lines,
+        &ours.sections[0].lines,
+        &theirs.sections[0].lines,
+    );
+
+    let b = section_ids(base);
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for ((name, occurrence), _) in b
+        .iter()
+        .chain(&section_ids(ours))
+        .chain(&section_ids(theirs))
+    {
+        let count = counts.entry(name).or_insert(0);
+        *count = (*count).max(occurrence + 1);
+    }
+    let repeated: BTreeSet<&str> = counts
+        .into_iter()
+        .filter(|&(_, count)| count > 1)
+        .map(|(name, _)| name)
+        .collect();
+    // repeated sections are matched by their content in base, not by their order
+    let (o, ours_ambiguous) = side_ids(base, &b, ours, &repeated);
+    let (t, theirs_ambiguous) = side_ids(base, &b, theirs, &repeated);
+
+    // sections of theirs to add after the section of ours they follow in theirs
+    let mut inserts: Vec<(usize, usize, Option<&Section>, bool)> = Vec::new();
+    for (pos, &(id, index)) in t.iter().enumerate() {
+        let section = &theirs.sections[index];
+        // an ambiguous section conflicts unless ours kept all it may come from
+        let conflict = theirs_ambiguous
+            .get(&index)
+            .is_some_and(|occurrences| !untouched(base, &b, ours, &o, section.name(), occurrences));
+        let base_section = find(&b, id).map(|index| &base.sections[index]);
+        let before = match find(&o, id) {
+            // both added a repeated section, keep both
+            Some(ours_index)
+                if base_section.is_none()
+                    && repeated.contains(id.0)
+                    && !same(&ours.sections[ours_index], section) =>
+            {
+                ours_index + 1
+            }
+            Some(_) => continue,
+            None if base_section.is_some_and(|base_section| same(base_section, section)) => {
+                continue
+            }
+            None => t[..pos]
+                .iter()
+                .rev()
+                .find_map(|&(id, _)| find(&o, id))
+                .map_or(1, |index| index + 1),
+        };
+        inserts.push((before, index, base_section, conflict));
+    }
+
+    for index in 1..=ours.sections.len() {
+        for &(_, theirs_index, base_section, conflict) in
+            inserts.iter().filter(|insert| insert.0 == index)
+        {
+            let section = &theirs.sections[theirs_index];
+            match base_section {
+                None if !conflict => merger.inserted(section, &theirs.sections[theirs_index - 1]),
+                _ => merger.section_conflict(section.name(), None, Some(section)),
+            }
+        }
+        let section = match ours.sections.get(index) {
+            Some(section) => section,
+            None => break,
+        };
+        if ours_ambiguous.get(&index).is_some_and(|occurrences| {
+            !untouched(base, &b, theirs, &t, section.name(), occurrences)
+        }) {
+            merger.section_conflict(section.name(), Some(section), None);
+            continue;
+        }
+        let id = o
+            .iter()
+            .find(|&&(_, i)| i == index)
+            .map(|&(id, _)| id)
+            .unwrap();
+        let base_section = find(&b, id).map(|index| &base.sections[index]);
+        let theirs_section = find(&t, id).map(|index| &theirs.sections[index]);
+        match (base_section, theirs_section) {
+            (Some(base_section), Some(theirs_section)) => {
+                merger.line(section.header.as_ref().unwrap());
+                merger.lines(
+                    section.name(),
+                    &base_section.lines,
+                    &section.lines,
+                    &theirs_section.lines,
+                );
+            }
+            (Some(base_section), None) => {
+                if !same(base_section, section) {
+                    merger.section_conflict(section.name(), Some(section), None);
+                }
+            }
+            (None, Some(theirs_section))
+                if !repeated.contains(section.name()) && !same(section, theirs_section) =>
+            {
+                merger.line(section.header.as_ref().unwrap());
+                merger.lines(section.name(), &[], &section.lines, &theirs_section.lines);
+            }
+            (None, _) => merger.section(section),
+        }
+    }
+
+    Merged {
+        text: merger.text,
+        conflicts: merger.conflicts,
+    }
+}
+
+/// Parses and merges, errors say which side failed to parse.
+#[cfg(feature = "std")]
+pub fn merge(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    options: &MergeOptions,
+) -> Result<Merged, String> {
+    let parse =
+        |side: &str, text| Document::parse(text).map_err(|err| format!("{}: {}", side, err));
+    Ok(merge_documents(
+        &parse("base", base)?,
+        &parse("ours", ours)?,
+        &parse("theirs", theirs)?,
+        options,
+    ))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "Version = 1\n\n[Header]\nMaxHexX  = 200\nMaxHexY  = 200\n\n[Object]\nPid = 1\nX = 10\n\n[Object]\nPid = 2\nX = 20\n";
+
+    #[test]
+    fn test_clean_merge() {
+        // ours retunes a key and adds an object, theirs edits other keys and objects
+        let ours = "Version = 1\n\n[Header]\nMaxHexX  = 300\nMaxHexY  = 200\n\n[Object]\nPid = 1\nX = 10\n\n[Object]\nPid = 2\nX = 20\n\n[Object]\nPid = 3\n";
+        let theirs = "Version = 2\n\n[Header]\nMaxHexX  = 200\nMaxHexY  = 200\nWorkHexX = 5\n\n[Object]\nPid = 1\nX = 11\n\n[Object]\nPid = 2\nX = 20\n\n[Object]\nPid = 4\n\n[Tiles]\nA = 1\n";
+        let merged = merge(BASE, ours, theirs, &MergeOptions::default()).unwrap();
+        assert!(merged.is_clean(), "{:?}", merged.conflicts);
+        assert_eq!(
+            merged.text,
+            "Version = 2\n\n[Header]\nMaxHexX  = 300\nMaxHexY  = 200\nWorkHexX = 5\n\n[Object]\nPid = 1\nX = 11\n\n[Object]\nPid = 2\nX = 20\n\n[Object]\nPid = 3\n\n[Object]\nPid = 4\n\n[Tiles]\nA = 1\n"
+        );
+
+        // deletions on one side go through when the other did not touch it
+        let ours = "Version = 1\n\n[Header]\nMaxHexX  = 200\n\n[Object]\nPid = 1\nX = 10\n";
+        let merged = merge(BASE, ours, BASE, &MergeOptions::default()).unwrap();
+        assert!(merged.is_clean());
+        assert_eq!(merged.text, ours);
+        // blank lines stay with the section above a deleted one
+        let merged = merge(BASE, BASE, ours, &MergeOptions::default()).unwrap();
+        assert_eq!(merged.text, format!("{}\n", ours));
+    }
+
+    #[test]
+    fn test_repeated_sections() {
+        // ours adds an object at the front, theirs edits the first one of base
+        let ours = "Version = 1\n\n[Header]\nMaxHexX  = 200\nMaxHexY  = 200\n\n[Object]\nPid = 3\nX = 10\n\n[Object]\nPid = 1\nX = 10\n\n[Object]\nPid = 2\nX = 20\n";
+        let theirs = BASE.replace("X = 10", "X = 11");
+        let merged = merge(BASE, ours, &theirs, &MergeOptions::default()).unwrap();
+        assert!(merged.is_clean(), "{:?}", merged.conflicts);
+        assert_eq!(
+            merged.text,
+            ours.replace("Pid = 1\nX = 10", "Pid = 1\nX = 11")
+        );
+
+        // ours deletes the first object, theirs edits the second one
+        let ours = BASE.replace("[Object]\nPid = 1\nX = 10\n\n", "");
+        let theirs = BASE.replace("X = 20", "X = 21");
+        let merged = merge(BASE, &ours, &theirs, &MergeOptions::default()).unwrap();
+        assert!(merged.is_clean(), "{:?}", merged.conflicts);
+        assert_eq!(merged.text, ours.replace("X = 20", "X = 21"));
+
+        // theirs' only object could come from either one of base, ours changed one
+        let base = "[Object]\nPid = 1\nX = 10\nY = 5\n[Object]\nPid = 1\nX = 20\nY = 5\n";
+        let ours = base.replace("X = 10", "X = 12");
+        let theirs = "[Object]\nPid = 1\nX = 21\nY = 5\n";
+        let merged = merge(base, &ours, theirs, &MergeOptions::default()).unwrap();
+        assert_eq!(merged.conflicts.len(), 2, "{}", merged.text);
+        assert!(merged.text.contains("X = 12") && merged.text.contains("X = 21"));
+        // without the change of ours it is just theirs
+        let merged = merge(base, base, theirs, &MergeOptions::default()).unwrap();
+        assert!(merged.is_clean(), "{:?}", merged.conflicts);
+        assert_eq!(merged.text, theirs);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let ours = "Version = 3\n\n[Header]\nMaxHexX  = 300\nMaxHexY  = 200\n\n[Object]\nPid = 1\nX = 12\n";
+        let theirs = "Version = 2\r\n\r\n[Header]\r\nMaxHexX  = 200\r\nMaxHexY  = 200\r\n\r\n[Object]\r\nPid = 1\r\nX = 10\r\n\r\n[Object]\r\nPid = 2\r\nX = 21\r\n";
+        let options = MergeOptions {
+            marker_size: 3,
+            ..MergeOptions::default()
+        };
+        let merged = merge(BASE, ours, theirs, &options).unwrap();
+        assert_eq!(
+            merged.text,
+            "<<< ours\nVersion = 3\n===\nVersion = 2\r\n>>> theirs\n\n[Header]\nMaxHexX  = 300\nMaxHexY  = 200\n\n[Object]\nPid = 1\nX = 12\n<<< ours\n===\n[Object]\r\nPid = 2\r\nX = 21\r\n>>> theirs\n"
+        );
+        assert_eq!(merged.conflicts.len(), 2);
+        assert_eq!(merged.conflicts[0].line, 1);
+        assert_eq!(merged.conflicts[0].key.as_deref(), Some("Version"));
+        assert_eq!(merged.conflicts[0].theirs.as_deref(), Some("2"));
+        assert_eq!(
+            merged.conflicts[1].to_string(),
+            "line 14: [Object] deleted by ours, changed by theirs"
+        );
+        assert!(merge("[A", "", "", &options)
+            .unwrap_err()
+            .starts_with("base: "));
+    }
+}
@@ -0,0 +1,117 @@
+use alloc::vec::Vec;
+
+use nom::{combinator::cut, error::ParseError, sequence::tuple, IResult, Offset};
+
+use crate::{
+    curly_delimited,
+    document::{line_endings, raw_line},
+    not_closing_curly, unsigned_number,
+};
+
+/// `{100}{}{Text}`, the text may span lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Message<'a> {
+    pub number: u32,
+    /// Usually empty, names a sound in some files.
+    pub audio: &'a str,
+    pub text: &'a str,
+    /// 1-based line of the opening `{`.
+    pub line: usize,
+    /// Byte offset of the opening `{`.
+    pub offset: usize,
+}
+
+/// Lines not starting with `{` are comments.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MsgTable<'a> {
+    pub messages: Vec<Message<'a>>,
+}
+
+impl<'a> MsgTable<'a> {
+    #[cfg(feature = "std")]
+    pub fn parse(text: &'a str) -> Result<Self, String> {
+        crate::nom_err_to_string(
+            text,
+            nom::combinator::all_consuming(msg_table::<nom::error::VerboseError<&str>>)(text),
+        )
+        .map(|(_, table)| table)
+    }
+
+    /// Text of the first message with `number`.
+    pub fn get(&self, number: u32) -> Option<&'a str> {
+        self.numbered(number).next().map(|message| message.text)
+    }
+
+    /// Numbers may repeat, e.g. for random replies.
+    pub fn numbered(&self, number: u32) -> impl Iterator<Item = &Message<'a>> + '_ {
+        self.messages
+            .iter()
+            .filter(move |message| message.number == number)
+    }
+}
+
+pub fn message<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (u32, &'a str, &'a str), E> {
+    tuple((
+        curly_delimited(unsigned_number),
+        curly_delimited(not_closing_curly),
+        curly_delimited(not_closing_curly),
+    ))(i)
+}
+
+pub fn msg_table<'a, E: ParseError<&'a str>>(text: &'a str) -> IResult<&'a str, MsgTable<'a>, E> {
+    let mut messages = Vec::new();
+    let mut i = text;
+    let mut line = 1;
+    while !i.is_empty() {
+        let start = i.trim_start_matches([' ', '\t']);
+        if start.starts_with('{') {
+            let (rest, (number, audio, body)) = cut(message)(start)?;
+            messages.push(Message {
+                number,
+                audio,
+                text: body,
+                line,
+                offset: text.offset(start),
+            });
+            line += line_endings(&start[..start.offset(rest)]);
+            i = rest;
+        }
+        // the rest of the line is a comment
+        if i.is_empty() {
+            break;
+        }
+        let (rest, (_, eol)) = raw_line(i)?;
+        line += !eol.is_empty() as usize;
+        i = rest;
+    }
+    Ok((i, MsgTable { messages }))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msg_table() {
+        let table = MsgTable::parse(
+            "# greetings\n{100}{}{Hello}\r\n  {101}{snd01}{Two\nlines} trailing\n\n{101}{}{Again}",
+        )
+        .unwrap();
+        assert_eq!(table.messages.len(), 3);
+        assert_eq!(table.get(100), Some("Hello"));
+        assert_eq!(table.messages[1].audio, "snd01");
+        assert_eq!(table.messages[1].text, "Two\nlines");
+        assert_eq!(table.messages[1].line, 3);
+        assert_eq!(table.messages[2].line, 6);
+        assert_eq!(table.numbered(101).count(), 2);
+        assert_eq!(MsgTable::parse("").unwrap().messages, []);
+        let table = MsgTable::parse("# old mac\r{100}{}{One\rtwo}\r{101}{}{Three}").unwrap();
+        assert_eq!(table.messages[0].line, 2);
+        assert_eq!(table.messages[1].line, 4);
+
+        let err = MsgTable::parse("{100}{}{ok}\n{1x}{}{bad}\n").unwrap_err();
+        assert_eq!(crate::error_line(&err), Some(2));
+    }
+}
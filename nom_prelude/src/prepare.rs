@@ -0,0 +1,224 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf8Bom,
+    Utf16LeBom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    /// Bare `\r`.
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrepareError {
+    /// UTF-16 input with an odd number of bytes.
+    OddLength,
+    /// Unpaired surrogate at this byte offset.
+    InvalidUtf16(usize),
+    /// Invalid UTF-8 at this byte offset.
+    InvalidUtf8(usize),
+}
+
+impl fmt::Display for PrepareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrepareError::OddLength => f.write_str("UTF-16 text with an odd number of bytes"),
+            PrepareError::InvalidUtf16(offset) => write!(f, "invalid UTF-16 at byte {}", offset),
+            PrepareError::InvalidUtf8(offset) => write!(f, "invalid UTF-8 at byte {}", offset),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PrepareError {}
+
+/// Text with BOM stripped and every line ending turned into `\n`, plus what it took
+/// to get there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prepared {
+    pub text: String,
+    pub encoding: Encoding,
+    /// Original ending of each line, the last line only if it had one.
+    pub endings: Vec<LineEnding>,
+}
+
+impl Prepared {
+    pub fn is_mixed(&self) -> bool {
+        self.endings.windows(2).any(|pair| pair[0] != pair[1])
+    }
+
+    /// The most frequent original ending, `\n` for text without any.
+    pub fn line_ending(&self) -> LineEnding {
+        let count = |ending| self.endings.iter().filter(|&&e| e == ending).count();
+        [LineEnding::CrLf, LineEnding::Cr]
+            .iter()
+            .copied()
+            .fold(LineEnding::Lf, |best, ending| {
+                if count(ending) > count(best) {
+                    ending
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// Writes `text` (usually an edited [`Prepared::text`]) back in the original
+    /// encoding. Line `n` gets the original ending of line `n`, new lines the most
+    /// frequent one.
+    pub fn restore(&self, text: &str) -> Vec<u8> {
+        let dominant = self.line_ending();
+        let mut restored = String::with_capacity(text.len() + self.endings.len());
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            match line.strip_suffix('\n') {
+                Some(line) => {
+                    restored.push_str(line);
+                    let ending = self.endings.get(index).copied().unwrap_or(dominant);
+                    restored.push_str(ending.as_str());
+                }
+                None => restored.push_str(line),
+            }
+        }
+        encode(&restored, self.encoding)
+    }
+}
+
+/// UTF-8 with or without BOM, UTF-16LE with BOM. The BOM is not part of the text.
+pub fn decode(bytes: &[u8]) -> Result<(String, Encoding), PrepareError> {
+    Ok(if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+        (decode_utf16le(rest)?, Encoding::Utf16LeBom)
+    } else if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        (decode_utf8(rest, UTF8_BOM.len())?, Encoding::Utf8Bom)
+    } else {
+        (decode_utf8(bytes, 0)?, Encoding::Utf8)
+    })
+}
+
+/// Inverse of [`decode`], line endings are left alone.
+pub fn encode(text: &str, encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Utf8 => text.as_bytes().to_vec(),
+        Encoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+        Encoding::Utf16LeBom => UTF16LE_BOM
+            .iter()
+            .copied()
+            .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect(),
+    }
+}
+
+/// Decodes `bytes` like [`decode`] and normalizes `\r\n` and bare `\r` to `\n`.
+pub fn prepare(bytes: &[u8]) -> Result<Prepared, PrepareError> {
+    let (text, encoding) = decode(bytes)?;
+    let (text, endings) = normalize_line_endings(&text);
+    Ok(Prepared {
+        text,
+        encoding,
+        endings,
+    })
+}
+
+fn decode_utf8(bytes: &[u8], offset: usize) -> Result<String, PrepareError> {
+    core::str::from_utf8(bytes)
+        .map(String::from)
+        .map_err(|err| PrepareError::InvalidUtf8(offset + err.valid_up_to()))
+}
+
+fn decode_utf16le(bytes: &[u8]) -> Result<String, PrepareError> {
+    let pairs = bytes.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(PrepareError::OddLength);
+    }
+    let units = pairs.map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut offset = UTF16LE_BOM.len();
+    for ch in char::decode_utf16(units) {
+        let ch = ch.map_err(|_| PrepareError::InvalidUtf16(offset))?;
+        offset += ch.len_utf16() * 2;
+        text.push(ch);
+    }
+    Ok(text)
+}
+
+/// `\r\n` and bare `\r` become `\n`, returns the original endings in order.
+pub fn normalize_line_endings(text: &str) -> (String, Vec<LineEnding>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut endings = Vec::new();
+    let mut rest = text;
+    while let Some(pos) = rest.find(['\r', '\n']) {
+        normalized.push_str(&rest[..pos]);
+        normalized.push('\n');
+        let ending = match &rest[pos..] {
+            tail if tail.starts_with("\r\n") => LineEnding::CrLf,
+            tail if tail.starts_with('\r') => LineEnding::Cr,
+            _ => LineEnding::Lf,
+        };
+        endings.push(ending);
+        rest = &rest[pos + ending.as_str().len()..];
+    }
+    normalized.push_str(rest);
+    (normalized, endings)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_utf8() {
+        let bytes = b"\xEF\xBB\xBF[Header]\r\nA = 1\rB = 2\r\nC = 3";
+        let prepared = prepare(bytes).unwrap();
+        assert_eq!(prepared.text, "[Header]\nA = 1\nB = 2\nC = 3");
+        assert_eq!(prepared.encoding, Encoding::Utf8Bom);
+        assert!(prepared.is_mixed());
+        assert_eq!(prepared.line_ending(), LineEnding::CrLf);
+        assert_eq!(prepared.restore(&prepared.text), bytes);
+        assert_eq!(
+            prepared.restore("[Header]\nA = 1\nB = 2\nC = 3\nD = 4\n"),
+            b"\xEF\xBB\xBF[Header]\r\nA = 1\rB = 2\r\nC = 3\r\nD = 4\r\n"
+        );
+
+        let plain = prepare(b"A\nB\n").unwrap();
+        assert_eq!(plain.encoding, Encoding::Utf8);
+        assert!(!plain.is_mixed());
+        assert_eq!(plain.line_ending(), LineEnding::Lf);
+        assert_eq!(prepare(b"A\xFF"), Err(PrepareError::InvalidUtf8(1)));
+    }
+
+    #[test]
+    fn test_prepare_utf16() {
+        let bytes: Vec<u8> = UTF16LE_BOM
+            .iter()
+            .copied()
+            .chain("Имя = Ё\r\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let prepared = prepare(&bytes).unwrap();
+        assert_eq!(prepared.text, "Имя = Ё\n");
+        assert_eq!(prepared.encoding, Encoding::Utf16LeBom);
+        assert_eq!(prepared.restore(&prepared.text), bytes);
+        assert_eq!(prepare(b"\xFF\xFEA"), Err(PrepareError::OddLength));
+        assert_eq!(
+            prepare(b"\xFF\xFEA\0\x00\xD8"),
+            Err(PrepareError::InvalidUtf16(4))
+        );
+    }
+}
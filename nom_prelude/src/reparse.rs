@@ -0,0 +1,237 @@
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use nom::{error::ParseError, IResult, Offset};
+
+use crate::document::{document, Document, Line, LineKind, Section};
+
+/// Replaces the byte `range` of the old text with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit<'e> {
+    pub range: Range<usize>,
+    pub text: &'e str,
+}
+
+impl Edit<'_> {
+    pub fn apply(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len() - self.range.len() + self.text.len());
+        out.push_str(&text[..self.range.start]);
+        out.push_str(self.text);
+        out.push_str(&text[self.range.end..]);
+        out
+    }
+}
+
+/// `sub` is a part of `raw`, the same part of `new_raw`.
+fn same_part<'b>(raw: &str, new_raw: &'b str, sub: &str) -> &'b str {
+    let start = raw.offset(sub);
+    &new_raw[start..start + sub.len()]
+}
+
+/// `line` moved unchanged to `offset` in `text`.
+fn rebase<'b>(line: &Line<'_>, text: &'b str, offset: usize, number: usize) -> Line<'b> {
+    let end = offset + line.raw.len();
+    let raw = &text[offset..end];
+    let part = |sub| same_part(line.raw, raw, sub);
+    let kind = match line.kind {
+        LineKind::Blank => LineKind::Blank,
+        LineKind::Comment(comment) => LineKind::Comment(part(comment)),
+        LineKind::Section(name) => LineKind::Section(part(name)),
+        LineKind::Entry {
+            key,
+            value,
+            separator,
+        } => LineKind::Entry {
+            key: part(key),
+            value: part(value),
+            separator,
+        },
+    };
+    Line {
+        kind,
+        raw,
+        eol: &text[end..end + line.eol.len()],
+        line: number,
+        offset,
+    }
+}
+
+fn end_of(line: &Line) -> usize {
+    line.offset + line.raw.len() + line.eol.len()
+}
+
+/// Parses `new_text`, which is the text of `old` after `edit`, reusing `old` for all
+/// lines the edit didn't touch. Only these lines are parsed again, unless a section
+/// header is among them or the edit doesn't fit `old`: then the whole text is.
+pub fn reparse_document<'b, E: ParseError<&'b str>>(
+    old: &Document,
+    new_text: &'b str,
+    edit: &Edit,
+) -> IResult<&'b str, Document<'b>, E> {
+    let lines: Vec<&Line> = old.lines().collect();
+    let old_len = lines.last().map_or(0, |line| end_of(line));
+    let Range { start, end } = edit.range;
+    if lines.is_empty()
+        || start > end
+        || end > old_len
+        || new_text.len() + (end - start) != old_len + edit.text.len()
+    {
+        return document(new_text);
+    }
+
+    // lines holding the edit, the first one starts at 0
+    let mut first = lines.partition_point(|line| line.offset <= start) - 1;
+    let mut last = lines.partition_point(|line| line.offset <= end) - 1;
+    let new_end_of = |last: usize| new_text.len() - (old_len - end_of(lines[last]));
+    // a "\r" and a "\n" meeting at either side of the region make one line ending
+    if first > 0
+        && new_text[..lines[first].offset].ends_with('\r')
+        && new_text[lines[first].offset..].starts_with('\n')
+    {
+        first -= 1;
+    }
+    if last + 1 < lines.len()
+        && new_text
+            .get(..new_end_of(last))
+            .is_some_and(|text| text.ends_with('\r'))
+        && new_text[new_end_of(last)..].starts_with('\n')
+    {
+        last += 1;
+    }
+    let region_start = lines[first].offset;
+    let (old_end, new_end) = (end_of(lines[last]), new_end_of(last));
+
+    let touched_header = lines[first..=last]
+        .iter()
+        .any(|line| matches!(line.kind, LineKind::Section(_)));
+    let region = match new_text.get(region_start..new_end) {
+        Some(region) if !touched_header => region,
+        _ => return document(new_text),
+    };
+    let (_, parsed) = document::<E>(region)?;
+    if parsed.sections.len() > 1 {
+        return document(new_text);
+    }
+    let first_number = lines[first].line;
+    let parsed: Vec<Line<'b>> = parsed.sections[0]
+        .lines
+        .iter()
+        .map(|line| Line {
+            line: line.line + first_number - 1,
+            offset: line.offset + region_start,
+            ..*line
+        })
+        .collect();
+
+    let removed = last + 1 - first;
+    let moved = |line: &Line, index: usize| {
+        if index < first {
+            rebase(line, new_text, line.offset, line.line)
+        } else {
+            let number = line.line + parsed.len() - removed;
+            rebase(line, new_text, line.offset - old_end + new_end, number)
+        }
+    };
+    let mut index = 0;
+    let mut sections = Vec::with_capacity(old.sections.len());
+    for section in &old.sections {
+        let header = section.header.as_ref().map(|header| {
+            index += 1;
+            moved(header, index - 1)
+        });
+        let mut lines = Vec::with_capacity(section.lines.len());
+        let mut rest = &section.lines[..];
+        while let Some((line, tail)) = rest.split_first() {
+            if index == first {
+                lines.extend_from_slice(&parsed);
+                index += removed;
+                rest = &rest[removed..];
+            } else {
+                lines.push(moved(line, index));
+                index += 1;
+                rest = tail;
+            }
+        }
+        sections.push(Section { header, lines });
+    }
+    Ok((&new_text[new_text.len()..], Document { sections }))
+}
+
+/// [`reparse_document`] with errors reported like [`Document::parse`] does.
+#[cfg(feature = "std")]
+pub fn reparse<'b>(old: &Document, new_text: &'b str, edit: &Edit) -> Result<Document<'b>, String> {
+    crate::nom_err_to_string(
+        new_text,
+        reparse_document::<nom::error::VerboseError<&str>>(old, new_text, edit),
+    )
+    .map(|(_, doc)| doc)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Version = 1\r\n# objects\r\n[Objects]\r\nPid = 1\r\nMapX = 10\r\n\r\n[Tiles]\r\nTile = 5\r\nLast";
+
+    fn check(text: &str, range: Range<usize>, replacement: &str) {
+        let old = Document::parse(text).unwrap();
+        let edit = Edit {
+            range,
+            text: replacement,
+        };
+        let new_text = edit.apply(text);
+        let full = Document::parse(&new_text);
+        assert_eq!(reparse(&old, &new_text, &edit), full, "{:?}", new_text);
+    }
+
+    #[test]
+    fn test_reparse() {
+        let at = |part: &str| TEXT.find(part).unwrap();
+        let map_x = at("10");
+        check(TEXT, map_x..map_x + 2, "12");
+        check(TEXT, map_x..map_x, "1\r\nMapY = 5\r\nMapZ = ");
+        check(TEXT, at("Pid")..at("MapX"), "");
+        check(TEXT, at("Tile ")..TEXT.len(), "");
+        check(TEXT, TEXT.len()..TEXT.len(), "\r\nMore = 1\r\n");
+        check(TEXT, 0..0, "// top\r\n");
+        check(TEXT, at("Pid") - 1..at("Pid"), "");
+        // section boundaries change
+        check(TEXT, at("[Tiles]")..at("[Tiles]") + 1, "");
+        check(TEXT, map_x..map_x, "\r\n[New]\r\nK = ");
+        check(TEXT, at("[Objects]") - 1..at("[Objects]"), " ");
+        // "\r" and "\n" meet
+        check("A = 1\r\n\nB = 2\n", 6..7, "");
+        check("A = 1\r\nB = 2\n", 5..5, "\r");
+        check("A = 1\rB = 2\n", 6..6, "\n");
+        // errors point into the new text
+        let old = Document::parse(TEXT).unwrap();
+        let edit = Edit {
+            range: map_x..map_x,
+            text: "1\r\n[",
+        };
+        let new_text = edit.apply(TEXT);
+        let err = reparse(&old, &new_text, &edit).unwrap_err();
+        assert_eq!(err, Document::parse(&new_text).unwrap_err());
+        assert_eq!(crate::error_line(&err), Some(6));
+    }
+
+    #[test]
+    fn test_reparse_reuses_lines() {
+        let old = Document::parse(TEXT).unwrap();
+        let at = TEXT.find("10").unwrap();
+        let edit = Edit {
+            range: at..at + 2,
+            text: "123",
+        };
+        let new_text = edit.apply(TEXT);
+        let new = reparse_document::<(&str, nom::error::ErrorKind)>(&old, &new_text, &edit)
+            .unwrap()
+            .1;
+        assert_eq!(new.section("Objects").unwrap().get("MapX"), Some("123"));
+        let tile = new.section("Tiles").unwrap().entries().next().unwrap().2;
+        assert_eq!(
+            (tile.line, tile.offset),
+            (8, TEXT.find("Tile ").unwrap() + 1)
+        );
+    }
+}
@@ -0,0 +1,739 @@
+use std::{collections::BTreeMap, fmt};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{char, one_of, space0, space1},
+    combinator::{all_consuming, map, not, opt, value},
+    error::{ErrorKind, ParseError},
+    multi::{many0, separated_list},
+    sequence::{delimited, pair, preceded, terminated},
+    IResult,
+};
+
+use crate::document::{Document, Section};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// 1-based, `None` for things that are missing altogether.
+    pub line: Option<usize>,
+    pub msg: String,
+}
+
+impl SchemaError {
+    fn new(line: Option<usize>, msg: String) -> Self {
+        SchemaError { line, msg }
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.msg),
+            None => f.write_str(&self.msg),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    /// `0`/`1` or `false`/`true`.
+    Bool,
+    String,
+    /// Whitespace separated.
+    List(Box<Type>),
+    /// One of the names, matched case-insensitively.
+    Enum(Vec<String>),
+    /// Any of the names separated by whitespace, `|` or `,`.
+    Flags(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    List(Vec<Value>),
+    Enum(String),
+    Flags(Vec<String>),
+}
+
+impl Value {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(int) => Some(*int),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(float) => Some(*float),
+            Value::Int(int) => Some(*int as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Strings and enum variants.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) | Value::Enum(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        match self {
+            Value::Flags(flags) => flags.iter().any(|f| f.eq_ignore_ascii_case(flag)),
+            _ => false,
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Value::Int(int) => Some(*int as f64),
+            Value::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+}
+
+/// Same syntax as in schema files.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => f.write_str("int"),
+            Type::Float => f.write_str("float"),
+            Type::Bool => f.write_str("bool"),
+            Type::String => f.write_str("string"),
+            Type::List(item) => write!(f, "list({})", item),
+            Type::Enum(names) => write!(f, "enum({})", names.join(" | ")),
+            Type::Flags(names) => write!(f, "flags({})", names.join(" | ")),
+        }
+    }
+}
+
+impl Type {
+    pub fn parse_value(&self, text: &str) -> Result<Value, String> {
+        let text = text.trim();
+        match self {
+            Type::Int => text
+                .parse()
+                .map(Value::Int)
+                .map_err(|_| format!("expected an integer, found {:?}", text)),
+            Type::Float => text
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| format!("expected a number, found {:?}", text)),
+            Type::Bool => match text {
+                "1" | "true" | "True" | "TRUE" => Ok(Value::Bool(true)),
+                "0" | "false" | "False" | "FALSE" => Ok(Value::Bool(false)),
+                _ => Err(format!("expected 0 or 1, found {:?}", text)),
+            },
+            Type::String => Ok(Value::String(text.to_owned())),
+            Type::List(item) => text
+                .split_whitespace()
+                .map(|text| item.parse_value(text))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            Type::Enum(names) => find_name(names, text)
+                .map(|name| Value::Enum(name.to_owned()))
+                .ok_or_else(|| format!("expected one of {}, found {:?}", names.join(", "), text)),
+            Type::Flags(names) => text
+                .split(|ch: char| ch.is_whitespace() || ch == '|' || ch == ',')
+                .filter(|flag| !flag.is_empty() && *flag != "0")
+                .map(|flag| {
+                    find_name(names, flag).map(str::to_owned).ok_or_else(|| {
+                        format!("unknown flag {:?}, expected {}", flag, names.join(", "))
+                    })
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::Flags),
+        }
+    }
+}
+
+fn find_name<'n>(names: &'n [String], text: &str) -> Option<&'n str> {
+    names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(text))
+        .map(String::as_str)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySchema {
+    pub name: String,
+    pub ty: Type,
+    pub required: bool,
+    pub default: Option<Value>,
+    /// Inclusive bounds of numbers, list items included.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Exact length of a list.
+    pub len: Option<usize>,
+}
+
+impl KeySchema {
+    fn check(&self, value: &Value) -> Result<(), String> {
+        if let (Some(len), Value::List(items)) = (self.len, value) {
+            if items.len() != len {
+                return Err(format!("expected {} items, found {}", len, items.len()));
+            }
+        }
+        let numbers: Vec<f64> = match value {
+            Value::List(items) => items.iter().filter_map(Value::number).collect(),
+            value => value.number().into_iter().collect(),
+        };
+        for number in numbers {
+            if self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max)
+            {
+                return Err(format!(
+                    "{} is out of range {}..={}",
+                    number,
+                    self.min.map_or(String::new(), |min| min.to_string()),
+                    self.max.map_or(String::new(), |max| max.to_string()),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SectionSchema {
+    /// `""` for the keys before the first section.
+    pub name: String,
+    pub required: bool,
+    pub multiple: bool,
+    /// Unknown keys are allowed.
+    pub open: bool,
+    pub keys: Vec<KeySchema>,
+}
+
+impl SectionSchema {
+    pub fn key(&self, name: &str) -> Option<&KeySchema> {
+        self.keys.iter().find(|key| key.name == name)
+    }
+}
+
+/// Describes a section format, itself written as one:
+///
+/// ```text
+/// [Header]
+/// @required = 1
+/// Version = int required min=1
+/// Kind = enum(Scenery | Item) default=Item
+/// DayTime = list(int) len=4 min=0 max=1439 default="0 360 720 1080"
+/// ```
+///
+/// `@required`, `@multiple` and `@open` describe the section, every other key a key
+/// of it. Unknown sections are errors unless the leading section says `@open = 1`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+    pub root: SectionSchema,
+    pub sections: Vec<SectionSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SectionValue {
+    pub name: String,
+    pub line: usize,
+    pub values: BTreeMap<String, Value>,
+}
+
+impl SectionValue {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+}
+
+/// Document checked against a [`Schema`], defaults filled in.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tree {
+    pub root: SectionValue,
+    pub sections: Vec<SectionValue>,
+}
+
+impl Tree {
+    pub fn section(&self, name: &str) -> Option<&SectionValue> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    pub fn sections<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s SectionValue> + 's {
+        self.sections
+            .iter()
+            .filter(move |section| section.name == name)
+    }
+}
+
+fn name<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, String, E> {
+    map(take_while1(is_name_char), String::from)(i)
+}
+
+fn names<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, Vec<String>, E> {
+    delimited(
+        space0,
+        separated_list(delimited(space0, one_of("|,"), space0), name),
+        space0,
+    )(i)
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// `word` not followed by more of a name: `int` but not `integer`.
+fn keyword<'a, E: ParseError<&'a str>>(
+    word: &'static str,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, E> {
+    terminated(tag(word), not(take_while1(is_name_char)))
+}
+
+pub fn type_spec<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, Type, E> {
+    alt((
+        value(Type::Int, keyword("int")),
+        value(Type::Float, keyword("float")),
+        value(Type::Bool, keyword("bool")),
+        value(Type::String, keyword("string")),
+        map(delimited(tag("list("), type_spec, char(')')), |item| {
+            Type::List(Box::new(item))
+        }),
+        map(delimited(tag("enum("), names, char(')')), Type::Enum),
+        map(delimited(tag("flags("), names, char(')')), Type::Flags),
+    ))(i)
+}
+
+/// `name`, `name=value` or `name="value with spaces"`, the quoted value can't hold `"`.
+fn option<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (&'a str, Option<&'a str>), E> {
+    pair(
+        take_while1(is_name_char),
+        opt(preceded(
+            char('='),
+            alt((
+                delimited(char('"'), take_while(|ch| ch != '"'), char('"')),
+                take_while1(|ch: char| !ch.is_whitespace() && ch != '"'),
+            )),
+        )),
+    )(i)
+}
+
+type KeySpec<'a> = (Type, Vec<(&'a str, Option<&'a str>)>);
+
+/// `type option...`
+pub fn key_spec<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, KeySpec<'a>, E> {
+    all_consuming(pair(
+        preceded(space0, type_spec),
+        many0(preceded(space1, option)),
+    ))(i.trim_end())
+}
+
+fn parse_key(name: &str, spec: &str) -> Result<KeySchema, String> {
+    let (_, (ty, options)) = key_spec::<(&str, ErrorKind)>(spec)
+        .map_err(|_| format!("invalid definition of `{}`: {:?}", name, spec))?;
+    let mut key = KeySchema {
+        name: name.to_owned(),
+        ty,
+        required: false,
+        default: None,
+        min: None,
+        max: None,
+        len: None,
+    };
+    for (option, arg) in options {
+        let number = |arg: Option<&str>| -> Result<f64, String> {
+            arg.and_then(|arg| arg.parse().ok())
+                .ok_or_else(|| format!("`{}` of `{}` needs a number", option, name))
+        };
+        match option {
+            "required" => key.required = true,
+            "min" => key.min = Some(number(arg)?),
+            "max" => key.max = Some(number(arg)?),
+            "len" => {
+                let len = arg
+                    .and_then(|arg| arg.parse().ok())
+                    .ok_or_else(|| format!("`len` of `{}` needs a non-negative integer", name))?;
+                key.len = Some(len);
+            }
+            "default" => {
+                let arg = arg.ok_or_else(|| format!("`default` of `{}` needs a value", name))?;
+                let default = key
+                    .ty
+                    .parse_value(arg)
+                    .map_err(|err| format!("default of `{}`: {}", name, err))?;
+                key.default = Some(default);
+            }
+            _ => return Err(format!("unknown option `{}` of `{}`", option, name)),
+        }
+    }
+    if let Some(default) = &key.default {
+        key.check(default)
+            .map_err(|err| format!("default of `{}`: {}", name, err))?;
+    }
+    Ok(key)
+}
+
+fn parse_section(section: &Section) -> Result<SectionSchema, SchemaError> {
+    let mut schema = SectionSchema {
+        name: section.name().to_owned(),
+        ..SectionSchema::default()
+    };
+    for (key, spec, line) in section.entries() {
+        let err = |msg| SchemaError::new(Some(line.line), msg);
+        if let Some(attribute) = key.strip_prefix('@') {
+            let flag = Type::Bool
+                .parse_value(spec)
+                .map_err(|msg| err(format!("{}: {}", key, msg)))?
+                == Value::Bool(true);
+            match attribute {
+                "required" => schema.required = flag,
+                "multiple" => schema.multiple = flag,
+                "open" => schema.open = flag,
+                _ => return Err(err(format!("unknown section attribute `{}`", key))),
+            }
+        } else if schema.key(key).is_some() {
+            return Err(err(format!("`{}` is defined twice", key)));
+        } else {
+            schema.keys.push(parse_key(key, spec).map_err(err)?);
+        }
+    }
+    Ok(schema)
+}
+
+fn parse_error(msg: String) -> SchemaError {
+    SchemaError::new(crate::error_line(&msg), msg)
+}
+
+impl Schema {
+    pub fn parse(text: &str) -> Result<Self, SchemaError> {
+        let doc = Document::parse(text).map_err(parse_error)?;
+        let mut schema = Schema {
+            root: parse_section(&doc.sections[0])?,
+            sections: Vec::new(),
+        };
+        for section in &doc.sections[1..] {
+            if schema.section(section.name()).is_some() {
+                return Err(SchemaError::new(
+                    Some(section.line()),
+                    format!("section [{}] is defined twice", section.name()),
+                ));
+            }
+            schema.sections.push(parse_section(section)?);
+        }
+        Ok(schema)
+    }
+
+    pub fn section(&self, name: &str) -> Option<&SectionSchema> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Parses `text` and validates it, see [`Schema::validate`].
+    pub fn parse_document(&self, text: &str) -> Result<Tree, Vec<SchemaError>> {
+        let doc = Document::parse(text).map_err(|msg| vec![parse_error(msg)])?;
+        self.validate(&doc)
+    }
+
+    /// Checks every section and key, collecting all problems instead of stopping at
+    /// the first one.
+    pub fn validate(&self, doc: &Document) -> Result<Tree, Vec<SchemaError>> {
+        let mut errors = Vec::new();
+        let mut tree = Tree {
+            root: validate_section(&self.root, &doc.sections[0], &mut errors),
+            sections: Vec::new(),
+        };
+        for section in &doc.sections[1..] {
+            match self.section(section.name()) {
+                Some(schema) => {
+                    if !schema.multiple && tree.section(section.name()).is_some() {
+                        errors.push(SchemaError::new(
+                            Some(section.line()),
+                            format!("section [{}] is repeated", section.name()),
+                        ));
+                    }
+                    tree.sections
+                        .push(validate_section(schema, section, &mut errors));
+                }
+                None if self.root.open => {}
+                None => errors.push(SchemaError::new(
+                    Some(section.line()),
+                    format!("unknown section [{}]", section.name()),
+                )),
+            }
+        }
+        for schema in &self.sections {
+            if schema.required && tree.section(&schema.name).is_none() {
+                errors.push(SchemaError::new(
+                    None,
+                    format!("missing section [{}]", schema.name),
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(tree)
+        } else {
+            errors.sort_by_key(|err| err.line.unwrap_or(usize::MAX));
+            Err(errors)
+        }
+    }
+}
+
+fn validate_section(
+    schema: &SectionSchema,
+    section: &Section,
+    errors: &mut Vec<SchemaError>,
+) -> SectionValue {
+    let in_section = if schema.name.is_empty() {
+        String::new()
+    } else {
+        format!(" in [{}]", schema.name)
+    };
+    let mut values = BTreeMap::new();
+    let mut seen: Vec<&str> = Vec::new();
+    for (key, text, line) in section.entries() {
+        let mut err = |msg: String| {
+            errors.push(SchemaError::new(
+                Some(line.line),
+                format!("`{}`{}: {}", key, in_section, msg),
+            ))
+        };
+        let key_schema = match schema.key(key) {
+            Some(key_schema) => key_schema,
+            None if schema.open => continue,
+            None => {
+                err("unknown key".to_owned());
+                continue;
+            }
+        };
+        if seen.contains(&key) {
+            err("repeated key".to_owned());
+            continue;
+        }
+        seen.push(key);
+        match key_schema
+            .ty
+            .parse_value(text)
+            .and_then(|value| key_schema.check(&value).map(|_| value))
+        {
+            Ok(value) => {
+                values.insert(key.to_owned(), value);
+            }
+            Err(msg) => err(msg),
+        }
+    }
+    for key in &schema.keys {
+        if seen.contains(&key.name.as_str()) {
+            continue;
+        }
+        match (&key.default, key.required) {
+            (Some(default), _) => {
+                values.insert(key.name.clone(), default.clone());
+            }
+            (None, true) => errors.push(SchemaError::new(
+                Some(section.line()),
+                format!("missing `{}`{}", key.name, in_section),
+            )),
+            (None, false) => {}
+        }
+    }
+    SectionValue {
+        name: section.name().to_owned(),
+        line: section.line(),
+        values,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "\
+        Comment = string\n\
+        [Header]\n\
+        @required = 1\n\
+        Version = int required min=1 max=10\n\
+        MaxHexX = int default=200 min=1\n\
+        NoLogout = bool default=0\n\
+        Scale = float min=0.5\n\
+        DayTime = list(int) len=4 min=0 max=1439\n\
+        [Object]\n\
+        @multiple = 1\n\
+        Kind = enum(Scenery | Item | Critter) required\n\
+        Flags = flags(NoBlock, ShootThru, Light)\n\
+        Name = string default=unnamed\n";
+
+    #[test]
+    fn test_schema() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+        assert_eq!(schema.root.keys.len(), 1);
+        let header = schema.section("Header").unwrap();
+        assert_eq!(header.key("DayTime").unwrap().ty.to_string(), "list(int)");
+        assert!(header.required && !header.multiple);
+        let version = header.key("Version").unwrap();
+        assert_eq!(version.ty, Type::Int);
+        assert!(version.required);
+        assert_eq!(version.max, Some(10.0));
+        let day_time = header.key("DayTime").unwrap();
+        assert_eq!(day_time.ty, Type::List(Box::new(Type::Int)));
+        assert_eq!(day_time.len, Some(4));
+        let object = schema.section("Object").unwrap();
+        assert_eq!(
+            object.key("Kind").unwrap().ty,
+            Type::Enum(vec!["Scenery".into(), "Item".into(), "Critter".into()])
+        );
+        assert_eq!(
+            object.key("Name").unwrap().default,
+            Some(Value::String("unnamed".into()))
+        );
+
+        let err = Schema::parse("[A]\nX = int\nY = integer\n").unwrap_err();
+        assert_eq!(err.line, Some(3));
+        for spec in [
+            "integer",
+            "intrequired",
+            "stringrequired",
+            "list(int)required",
+        ] {
+            assert!(key_spec::<(&str, ErrorKind)>(spec).is_err(), "{}", spec);
+        }
+        let (_, (ty, options)) = key_spec::<(&str, ErrorKind)>(" list(int)  required ").unwrap();
+        assert_eq!(
+            (ty.to_string(), options),
+            ("list(int)".into(), vec![("required", None)])
+        );
+        let err = Schema::parse("[A]\nX = int default=abc\n").unwrap_err();
+        assert!(err.msg.contains("default"), "{}", err);
+        let err = Schema::parse("[A]\nX = int max=1 default=5\n").unwrap_err();
+        assert!(err.msg.contains("out of range"), "{}", err);
+        for len in ["-1", "2.5", "x"] {
+            let err = Schema::parse(&format!("[A]\nX = list(int) len={}\n", len)).unwrap_err();
+            assert!(err.msg.contains("non-negative integer"), "{}: {}", len, err);
+        }
+
+        let schema = Schema::parse(
+            "[A]\nName = string default=\"Some name\"\nDays = list(int) len=2 default=\"1 2\" max=5\nEmpty = string default=\"\"\n",
+        )
+        .unwrap();
+        let a = schema.section("A").unwrap();
+        assert_eq!(
+            a.key("Name").unwrap().default,
+            Some(Value::String("Some name".into()))
+        );
+        assert_eq!(
+            a.key("Days").unwrap().default,
+            Some(Value::List(vec![Value::Int(1), Value::Int(2)]))
+        );
+        assert_eq!(a.key("Days").unwrap().max, Some(5.0));
+        assert_eq!(
+            a.key("Empty").unwrap().default,
+            Some(Value::String(String::new()))
+        );
+        assert!(Schema::parse("[A]\nName = string default=\"open\n").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+        let tree = schema
+            .parse_document(
+                "Comment = test\n\
+                 [Header]\n\
+                 Version = 3\n\
+                 Scale = 1.5\n\
+                 DayTime = 300 600 1140 1380\n\
+                 [Object]\n\
+                 Kind = item\n\
+                 Flags = NoBlock | light\n\
+                 [Object]\n\
+                 Kind = Scenery\n\
+                 Name = Rock\n",
+            )
+            .unwrap();
+        assert_eq!(
+            tree.root.get("Comment"),
+            Some(&Value::String("test".into()))
+        );
+        let header = tree.section("Header").unwrap();
+        assert_eq!(header.get("Version").and_then(Value::as_int), Some(3));
+        assert_eq!(header.get("MaxHexX").and_then(Value::as_int), Some(200));
+        assert_eq!(header.get("NoLogout").and_then(Value::as_bool), Some(false));
+        assert_eq!(header.get("Scale").and_then(Value::as_float), Some(1.5));
+        assert_eq!(
+            header
+                .get("DayTime")
+                .and_then(Value::as_list)
+                .map(<[_]>::len),
+            Some(4)
+        );
+        let objects: Vec<_> = tree.sections("Object").collect();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].get("Kind").and_then(Value::as_str), Some("Item"));
+        assert!(objects[0].get("Flags").unwrap().has_flag("Light"));
+        assert_eq!(
+            objects[0].get("Name").and_then(Value::as_str),
+            Some("unnamed")
+        );
+        assert_eq!(objects[1].line, 9);
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+        let errors = schema
+            .parse_document(
+                "Unknown = 1\n\
+                 [Header]\n\
+                 Version = 11\n\
+                 Version = 2\n\
+                 Scale = big\n\
+                 DayTime = 1 2 3\n\
+                 [Header]\n\
+                 Version = 1\n\
+                 [Object]\n\
+                 Flags = Heavy\n\
+                 [Map]\n",
+            )
+            .unwrap_err();
+        let lines: Vec<_> = errors.iter().map(|err| err.line).collect();
+        assert_eq!(
+            lines,
+            [
+                Some(1),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+                Some(9),
+                Some(10),
+                Some(11)
+            ],
+            "{:#?}",
+            errors
+        );
+        assert!(errors[1].msg.contains("out of range"), "{}", errors[1]);
+        assert!(errors[6].msg.contains("missing `Kind`"), "{}", errors[6]);
+
+        let errors = schema.parse_document("Comment = x\n").unwrap_err();
+        assert_eq!(errors[0].msg, "missing section [Header]");
+    }
+}
@@ -0,0 +1,773 @@
+use std::fmt;
+
+use serde::ser::{self, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub msg: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+fn error<T>(msg: impl Into<String>) -> Result<T, Error> {
+    Err(Error { msg: msg.into() })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `Key = Value`, read by `kv_eq`.
+    Equals,
+    /// `Key=Value`, read by `kv_eq`.
+    Tight,
+    /// `Key Value`, read by `kv`.
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Field order of the struct, insertion order of the map.
+    Declared,
+    Sorted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// Shortest text that parses back to the same value.
+    Shortest,
+    /// Fixed number of fractional digits.
+    Fixed(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoolFormat {
+    /// `1` / `0`, read by `int_bool`.
+    Int,
+    /// `true` / `false`.
+    Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoneFormat {
+    /// `Key = -`
+    Dash,
+    /// No line at all, the field needs `#[serde(default)]` to read back.
+    Omit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeqFormat {
+    /// `Key = 1 2 3`, read by `fixed_list_of_numbers`.
+    Joined,
+    /// One `Key = 1` line per element.
+    Repeated,
+    /// `Key0 = 1`, `Key1 = 2`, ...
+    Indexed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub separator: Separator,
+    pub key_order: KeyOrder,
+    pub floats: FloatFormat,
+    pub bools: BoolFormat,
+    pub none: NoneFormat,
+    pub seqs: SeqFormat,
+    pub line_ending: &'static str,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            separator: Separator::Equals,
+            key_order: KeyOrder::Declared,
+            floats: FloatFormat::Shortest,
+            bools: BoolFormat::Int,
+            none: NoneFormat::Dash,
+            seqs: SeqFormat::Joined,
+            line_ending: "\n",
+        }
+    }
+}
+
+/// Inverse of [`crate::de::from_str`]: struct fields holding structs become
+/// `[Section]`s, sequences of structs repeated sections, everything else keys.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    to_string_with(value, &Config::default())
+}
+
+pub fn to_string_with<T: Serialize + ?Sized>(value: &T, config: &Config) -> Result<String, Error> {
+    let fields = match value.serialize(ValueSerializer { config })? {
+        Value::Struct(fields) => fields,
+        _ => return error("top level value must be a struct or a map"),
+    };
+    let mut root = Vec::new();
+    let mut sections = Vec::new();
+    for (name, value) in fields {
+        match value {
+            Value::Struct(keys) => sections.push((name, keys)),
+            Value::Seq(items) if !items.is_empty() && items.iter().all(Value::is_struct) => {
+                for item in items {
+                    if let Value::Struct(keys) = item {
+                        sections.push((name.clone(), keys));
+                    }
+                }
+            }
+            value => root.push((name, value)),
+        }
+    }
+
+    let mut writer = Writer {
+        config,
+        out: String::new(),
+    };
+    writer.keys(root)?;
+    for (index, (name, keys)) in sections.into_iter().enumerate() {
+        if index > 0 || !writer.out.is_empty() {
+            writer.out.push_str(config.line_ending);
+        }
+        if name.is_empty() || name.contains(']') || name.trim() != name {
+            return error(format!("invalid section name {:?}", name));
+        }
+        writer.line(&format!("[{}]", name))?;
+        writer.keys(keys)?;
+    }
+    Ok(writer.out)
+}
+
+enum Value {
+    None,
+    Scalar(String),
+    Seq(Vec<Value>),
+    Struct(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn is_struct(&self) -> bool {
+        matches!(self, Value::Struct(_))
+    }
+}
+
+struct Writer<'c> {
+    config: &'c Config,
+    out: String,
+}
+
+impl Writer<'_> {
+    fn line(&mut self, line: &str) -> Result<(), Error> {
+        if line.contains(['\r', '\n']) {
+            return error(format!("line break in {:?}", line));
+        }
+        self.out.push_str(line);
+        self.out.push_str(self.config.line_ending);
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        // or it would read back as a header or a comment
+        let starts_line = ["[", "#", ";", "//"]
+            .iter()
+            .any(|start| key.starts_with(start));
+        if key.is_empty() || starts_line || key.contains(|ch: char| ch.is_whitespace() || ch == '=')
+        {
+            return error(format!("invalid key {:?}", key));
+        }
+        if value.trim() != value {
+            return error(format!(
+                "{}: value {:?} has surrounding whitespace",
+                key, value
+            ));
+        }
+        if self.config.separator == Separator::Space && value.starts_with('=') {
+            return error(format!(
+                "{}: value {:?} would read back without its `=`",
+                key, value
+            ));
+        }
+        let line = match (self.config.separator, value.is_empty()) {
+            (Separator::Equals, false) => format!("{} = {}", key, value),
+            (Separator::Equals, true) => format!("{} =", key),
+            (Separator::Tight, _) => format!("{}={}", key, value),
+            (Separator::Space, _) => format!("{} {}", key, value),
+        };
+        self.line(&line)
+    }
+
+    fn keys(&mut self, mut keys: Vec<(String, Value)>) -> Result<(), Error> {
+        if self.config.key_order == KeyOrder::Sorted {
+            keys.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+        for (key, value) in keys {
+            match value {
+                Value::None => match self.config.none {
+                    NoneFormat::Dash => self.key(&key, "-")?,
+                    NoneFormat::Omit => {}
+                },
+                Value::Scalar(value) => self.key(&key, &value)?,
+                Value::Seq(items) => {
+                    let items = items
+                        .into_iter()
+                        .map(|item| match item {
+                            Value::None => Ok("-".to_owned()),
+                            Value::Scalar(value) => Ok(value),
+                            _ => error(format!("{}: nested value in a list", key)),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    match self.config.seqs {
+                        SeqFormat::Joined => {
+                            // items are split on whitespace when read back
+                            if let Some(item) = items
+                                .iter()
+                                .find(|item| item.is_empty() || item.contains(char::is_whitespace))
+                            {
+                                return error(format!(
+                                    "{}: list item {:?} can't be joined, use `SeqFormat::Repeated`",
+                                    key, item
+                                ));
+                            }
+                            self.key(&key, &items.join(" "))?
+                        }
+                        SeqFormat::Repeated => {
+                            for item in &items {
+                                self.key(&key, item)?;
+                            }
+                        }
+                        SeqFormat::Indexed => {
+                            for (index, item) in items.iter().enumerate() {
+                                self.key(&format!("{}{}", key, index), item)?;
+                            }
+                        }
+                    }
+                }
+                Value::Struct(_) => return error(format!("{}: nested struct in a section", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+struct ValueSerializer<'c> {
+    config: &'c Config,
+}
+
+impl<'c> ValueSerializer<'c> {
+    fn scalar(self, value: impl fmt::Display) -> Result<Value, Error> {
+        Ok(Value::Scalar(value.to_string()))
+    }
+
+    fn float(self, value: f64) -> Result<Value, Error> {
+        match self.config.floats {
+            FloatFormat::Shortest => self.scalar(value),
+            FloatFormat::Fixed(digits) => self.scalar(format_args!("{:.*}", digits, value)),
+        }
+    }
+}
+
+impl<'c> ser::Serializer for ValueSerializer<'c> {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'c>;
+    type SerializeTuple = SeqSerializer<'c>;
+    type SerializeTupleStruct = SeqSerializer<'c>;
+    type SerializeTupleVariant = ser::Impossible<Value, Error>;
+    type SerializeMap = StructSerializer<'c>;
+    type SerializeStruct = StructSerializer<'c>;
+    type SerializeStructVariant = ser::Impossible<Value, Error>;
+
+    fn serialize_bool(self, value: bool) -> Result<Value, Error> {
+        match (self.config.bools, value) {
+            (BoolFormat::Int, value) => self.scalar(value as u8),
+            (BoolFormat::Word, value) => self.scalar(value),
+        }
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Value, Error> {
+        match self.config.floats {
+            // f32 -> f64 would print the widening noise
+            FloatFormat::Shortest => self.scalar(value),
+            FloatFormat::Fixed(_) => self.float(value.into()),
+        }
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Value, Error> {
+        self.float(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Value, Error> {
+        self.scalar(value)
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<Value, Error> {
+        error("bytes are not supported")
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        self.scalar("")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        self.scalar(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Value, Error> {
+        error(format!(
+            "{}::{}: enum variants with data are not supported",
+            name, variant
+        ))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'c>, Error> {
+        Ok(SeqSerializer {
+            config: self.config,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'c>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'c>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        error(format!(
+            "{}::{}: enum variants with data are not supported",
+            name, variant
+        ))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<StructSerializer<'c>, Error> {
+        Ok(StructSerializer {
+            config: self.config,
+            fields: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<StructSerializer<'c>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        error(format!(
+            "{}::{}: enum variants with data are not supported",
+            name, variant
+        ))
+    }
+}
+
+struct SeqSerializer<'c> {
+    config: &'c Config,
+    items: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let config = self.config;
+        self.items
+            .push(value.serialize(ValueSerializer { config })?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Seq(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct StructSerializer<'c> {
+    config: &'c Config,
+    fields: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for StructSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let config = self.config;
+        match key.serialize(ValueSerializer { config })? {
+            Value::Scalar(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => error("map keys must be scalars"),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let config = self.config;
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value before serialize_key");
+        self.fields
+            .push((key, value.serialize(ValueSerializer { config })?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Struct(self.fields))
+    }
+}
+
+impl ser::SerializeStruct for StructSerializer<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let config = self.config;
+        self.fields
+            .push((key.to_owned(), value.serialize(ValueSerializer { config })?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Struct(self.fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use nom::error::VerboseError;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{de::from_str, integer, kv, kv_eq, section, some_text};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Kind {
+        Scenery,
+        Item,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "PascalCase")]
+    struct Header {
+        version: u32,
+        max_hex_x: u16,
+        script_module: Option<String>,
+        day_time: Vec<i32>,
+        no_logout: bool,
+        scale: f32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "PascalCase")]
+    struct Object {
+        map_x: u16,
+        kind: Kind,
+        name: String,
+        #[serde(default)]
+        item: Vec<u32>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Map {
+        #[serde(rename = "Comment")]
+        comment: String,
+        #[serde(rename = "Header")]
+        header: Header,
+        #[serde(rename = "Object")]
+        objects: Vec<Object>,
+    }
+
+    fn map() -> Map {
+        Map {
+            comment: "some map".into(),
+            header: Header {
+                version: 4,
+                max_hex_x: 200,
+                script_module: None,
+                day_time: vec![300, 600, 1140, 1380],
+                no_logout: true,
+                scale: 1.5,
+            },
+            objects: vec![
+                Object {
+                    map_x: 10,
+                    kind: Kind::Scenery,
+                    name: "Big rock".into(),
+                    item: vec![10, 20],
+                },
+                Object {
+                    map_x: 12,
+                    kind: Kind::Item,
+                    name: "Gun".into(),
+                    item: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_to_string() {
+        let text = to_string(&map()).unwrap();
+        assert_eq!(
+            text,
+            "Comment = some map\n\
+             \n\
+             [Header]\n\
+             Version = 4\n\
+             MaxHexX = 200\n\
+             ScriptModule = -\n\
+             DayTime = 300 600 1140 1380\n\
+             NoLogout = 1\n\
+             Scale = 1.5\n\
+             \n\
+             [Object]\n\
+             MapX = 10\n\
+             Kind = Scenery\n\
+             Name = Big rock\n\
+             Item = 10 20\n\
+             \n\
+             [Object]\n\
+             MapX = 12\n\
+             Kind = Item\n\
+             Name = Gun\n\
+             Item =\n"
+        );
+        assert_eq!(from_str::<Map>(&text).unwrap(), map());
+
+        let (rest, _) = kv_eq::<VerboseError<&str>, _, _>("Comment", some_text)(&text).unwrap();
+        let (rest, _) = crate::t_rn::<_, VerboseError<&str>>(rest).unwrap();
+        let (rest, _) = section::<VerboseError<&str>>("Header")(rest).unwrap();
+        let (_, version) = kv_eq::<VerboseError<&str>, u32, _>("Version", integer)(rest).unwrap();
+        assert_eq!(version, 4);
+    }
+
+    #[test]
+    fn test_config() {
+        let config = Config {
+            separator: Separator::Space,
+            key_order: KeyOrder::Sorted,
+            floats: FloatFormat::Fixed(2),
+            bools: BoolFormat::Word,
+            none: NoneFormat::Omit,
+            seqs: SeqFormat::Indexed,
+            line_ending: "\r\n",
+        };
+        let text = to_string_with(&map(), &config).unwrap();
+        assert!(text.starts_with(
+            "Comment some map\r\n\
+             \r\n\
+             [Header]\r\n\
+             DayTime0 300\r\n\
+             DayTime1 600\r\n\
+             DayTime2 1140\r\n\
+             DayTime3 1380\r\n\
+             MaxHexX 200\r\n\
+             NoLogout true\r\n\
+             Scale 1.50\r\n\
+             Version 4\r\n"
+        ));
+        let header = &text[text.find("[Header]").unwrap()..];
+        let (rest, _) = section::<VerboseError<&str>>("Header")(header).unwrap();
+        let (_, day) = kv::<VerboseError<&str>, u32, _>("DayTime0", integer)(rest).unwrap();
+        assert_eq!(day, 300);
+
+        let parsed: Map = from_str(&text).unwrap();
+        assert_eq!(parsed.header.script_module, None);
+        assert_eq!(parsed.header.day_time, map().header.day_time);
+        assert_eq!(parsed.objects, map().objects);
+
+        let config = Config {
+            separator: Separator::Tight,
+            seqs: SeqFormat::Repeated,
+            ..Config::default()
+        };
+        let text = to_string_with(&map(), &config).unwrap();
+        assert!(text.contains("DayTime=300\nDayTime=600\n"));
+        assert_eq!(from_str::<Map>(&text).unwrap(), map());
+    }
+
+    #[test]
+    fn test_map_and_errors() {
+        let mut doc = BTreeMap::new();
+        doc.insert("Options", BTreeMap::from([("B", "2"), ("A", "1")]));
+        let text = to_string(&doc).unwrap();
+        assert_eq!(text, "[Options]\nA = 1\nB = 2\n");
+
+        let err = to_string(&5).unwrap_err();
+        assert!(err.msg.contains("top level"), "{}", err);
+        doc.insert("Bad", BTreeMap::from([("Key", "two\nlines")]));
+        assert!(to_string(&doc).is_err());
+
+        let invalid = |key: &str| {
+            let doc = BTreeMap::from([("S", BTreeMap::from([(key, "1")]))]);
+            to_string(&doc).is_err()
+        };
+        assert!(["[Key", "#Key", ";Key", "//Key", "K=", ""]
+            .iter()
+            .all(|key| invalid(key)));
+        assert!(!invalid("Key"));
+        assert!(to_string(&BTreeMap::from([("A]B", BTreeMap::from([("K", "1")]))])).is_err());
+
+        #[derive(Serialize)]
+        struct Names {
+            names: Vec<&'static str>,
+        }
+        let names = |names| Names { names };
+        assert!(to_string(&names(vec!["a b"])).is_err());
+        assert!(to_string(&names(vec!["a", ""])).is_err());
+        let repeated = Config {
+            seqs: SeqFormat::Repeated,
+            ..Config::default()
+        };
+        assert_eq!(
+            to_string_with(&names(vec!["a b", "c"]), &repeated).unwrap(),
+            "names = a b\nnames = c\n"
+        );
+        let spaced = Config {
+            separator: Separator::Space,
+            ..Config::default()
+        };
+        assert!(to_string_with(&names(vec!["=x"]), &spaced).is_err());
+        assert_eq!(to_string(&names(vec!["=x"])).unwrap(), "names = =x\n");
+    }
+}
@@ -0,0 +1,277 @@
+use core::cell::RefCell;
+use std::fmt;
+
+use nom::IResult;
+
+use crate::TraceInput;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok { consumed: usize },
+    Error,
+    Failure,
+    Incomplete,
+}
+
+/// One call of a [`traced`] parser and the traced calls it made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub name: &'static str,
+    /// Position of the input it got, from the start of the traced input.
+    pub start: usize,
+    pub outcome: Outcome,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Trace {
+    pub roots: Vec<Node>,
+}
+
+struct Recorder {
+    len: usize,
+    /// Calls that haven't returned yet, innermost last.
+    open: Vec<Node>,
+    roots: Vec<Node>,
+}
+
+std::thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Puts back the recorder of an outer [`trace`], also when the parser panics.
+struct Restore(Option<Option<Recorder>>);
+
+impl Restore {
+    fn finish(mut self) -> Option<Recorder> {
+        let outer = self.0.take().expect("finished once");
+        RECORDER.with(|cell| cell.replace(outer))
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(outer) = self.0.take() {
+            RECORDER.with(|cell| cell.replace(outer));
+        }
+    }
+}
+
+/// Runs `parser` on `input` recording calls of [`traced`] parsers. Only this thread is
+/// recorded, nested runs record separately.
+pub fn trace<I: TraceInput, O, E, F>(input: I, parser: F) -> (IResult<I, O, E>, Trace)
+where
+    F: Fn(I) -> IResult<I, O, E>,
+{
+    let recorder = Recorder {
+        len: input.input_len(),
+        open: Vec::new(),
+        roots: Vec::new(),
+    };
+    let restore = Restore(Some(RECORDER.with(|cell| cell.replace(Some(recorder)))));
+    let res = parser(input);
+    let recorder = restore.finish().expect("set above");
+    (
+        res,
+        Trace {
+            roots: recorder.roots,
+        },
+    )
+}
+
+/// `parser`, recorded under `name` when run by [`trace`]. Wrap the alternatives of an
+/// `alt` to see which of them were tried.
+pub fn traced<I: TraceInput, O, E, F>(
+    name: &'static str,
+    parser: F,
+) -> impl Fn(I) -> IResult<I, O, E>
+where
+    F: Fn(I) -> IResult<I, O, E>,
+{
+    move |i: I| {
+        let remaining = i.input_len();
+        let recording = RECORDER.with(|cell| match cell.borrow_mut().as_mut() {
+            Some(recorder) => {
+                recorder.open.push(Node {
+                    name,
+                    start: recorder.len.saturating_sub(remaining),
+                    outcome: Outcome::Incomplete,
+                    children: Vec::new(),
+                });
+                true
+            }
+            None => false,
+        });
+        let res = parser(i);
+        if recording {
+            let outcome = match &res {
+                Ok((rest, _)) => Outcome::Ok {
+                    consumed: remaining.saturating_sub(rest.input_len()),
+                },
+                Err(nom::Err::Error(_)) => Outcome::Error,
+                Err(nom::Err::Failure(_)) => Outcome::Failure,
+                Err(nom::Err::Incomplete(_)) => Outcome::Incomplete,
+            };
+            RECORDER.with(|cell| {
+                if let Some(recorder) = cell.borrow_mut().as_mut() {
+                    let mut node = recorder.open.pop().expect("entered above");
+                    node.outcome = outcome;
+                    match recorder.open.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => recorder.roots.push(node),
+                    }
+                }
+            });
+        }
+        res
+    }
+}
+
+impl Node {
+    fn write_text(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(
+            f,
+            "{:width$}{} at {}: ",
+            "",
+            self.name,
+            self.start,
+            width = depth * 2
+        )?;
+        match self.outcome {
+            Outcome::Ok { consumed } => writeln!(f, "ok, consumed {}", consumed)?,
+            Outcome::Error => writeln!(f, "error")?,
+            Outcome::Failure => writeln!(f, "failure")?,
+            Outcome::Incomplete => writeln!(f, "incomplete")?,
+        }
+        for child in &self.children {
+            child.write_text(f, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, out: &mut String) {
+        out.push_str("{\"name\":");
+        push_json_str(out, self.name);
+        out.push_str(&format!(",\"start\":{},\"result\":", self.start));
+        match self.outcome {
+            Outcome::Ok { consumed } => {
+                out.push_str(&format!("\"ok\",\"consumed\":{}", consumed));
+            }
+            Outcome::Error => out.push_str("\"error\""),
+            Outcome::Failure => out.push_str("\"failure\""),
+            Outcome::Incomplete => out.push_str("\"incomplete\""),
+        }
+        out.push_str(",\"children\":");
+        write_json_list(&self.children, out);
+        out.push('}');
+    }
+}
+
+fn push_json_str(out: &mut String, text: &str) {
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+fn write_json_list(nodes: &[Node], out: &mut String) {
+    out.push('[');
+    for (index, node) in nodes.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        node.write_json(out);
+    }
+    out.push(']');
+}
+
+impl Trace {
+    /// `[{"name", "start", "result", "consumed" (when ok), "children"}]`
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write_json_list(&self.roots, &mut out);
+        out
+    }
+}
+
+/// One call per line, indented by depth: `kv at 12: ok, consumed 7`.
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for root in &self.roots {
+            root.write_text(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseError;
+
+    use super::*;
+    use crate::{alt, complete::*, cut_apply, separated_list_first_unchecked};
+
+    #[test]
+    fn test_trace() {
+        let value = |i| {
+            alt((
+                traced("number", digit1::<_, VerboseError<&str>>),
+                traced("word", alphanumeric1),
+            ))(i)
+        };
+        let list = separated_list_first_unchecked(char(','), value);
+        let (res, recorded) = trace("1,a,", |mut i| {
+            let items = cut_apply(&mut i, &list)?;
+            Ok((i, items))
+        });
+        assert_eq!(res.unwrap().1, ["1", "a"]);
+        assert_eq!(
+            recorded.to_string(),
+            "\
+cut_apply at 0: ok, consumed 3
+  separated_list_first_unchecked at 0: ok, consumed 3
+    item at 0: ok, consumed 1
+      number at 0: ok, consumed 1
+    separator at 1: ok, consumed 1
+    item at 2: ok, consumed 1
+      number at 2: error
+      word at 2: ok, consumed 1
+    separator at 3: ok, consumed 1
+    item at 4: error
+      number at 4: error
+      word at 4: error
+"
+        );
+        assert!(recorded.to_json().starts_with(
+            "[{\"name\":\"cut_apply\",\"start\":0,\"result\":\"ok\",\"consumed\":3,\"children\":[{"
+        ));
+
+        // nothing is recorded outside of `trace`
+        assert!(traced("number", digit1::<_, VerboseError<&str>>)("1").is_ok());
+        let (res, recorded) = trace("x", |mut i| {
+            let digits = cut_apply(&mut i, digit1::<_, VerboseError<&str>>)?;
+            Ok((i, digits))
+        });
+        assert!(matches!(res, Err(nom::Err::Failure(_))));
+        assert_eq!(recorded.roots[0].outcome, Outcome::Failure);
+    }
+
+    #[test]
+    fn test_trace_panic() {
+        let panicked = std::panic::catch_unwind(|| {
+            trace("1", |i| -> IResult<&str, (), VerboseError<&str>> {
+                traced("number", digit1)(i)?;
+                panic!("in the parser")
+            })
+        });
+        assert!(panicked.is_err());
+        assert!(RECORDER.with(|cell| cell.borrow().is_none()));
+    }
+}